@@ -0,0 +1,188 @@
+//! Single-elimination bracket layout and standings.
+//!
+//! A bracket with `size` slots (a power of two) is stored as a heap-ordered array of
+//! `2 * size - 1` pubkeys: node 0 holds the champion, node `k` is decided by the match between
+//! nodes `2k + 1` and `2k + 2`, and the seeded players sit in the leaves `size - 1..2 * size - 1`.
+//! Undecided matches and byes hold `Pubkey::default()`.
+
+use anchor_lang::prelude::*;
+
+use crate::payout::placements;
+use crate::{ErrorCode, Winner};
+
+/// Largest field a bracket account can hold within the 10KB account creation limit.
+pub const MAX_BRACKET_PLAYERS: u32 = 128;
+
+pub fn bracket_size(player_count: u32) -> Result<u32> {
+    require!(
+        (2..=MAX_BRACKET_PLAYERS).contains(&player_count),
+        ErrorCode::InvalidBracketSize
+    );
+    Ok(player_count.next_power_of_two())
+}
+
+pub fn parent(node: usize) -> usize {
+    (node - 1) / 2
+}
+
+pub fn children(node: usize) -> (usize, usize) {
+    (2 * node + 1, 2 * node + 2)
+}
+
+/// Leaf that holds seed `seed` (0 being the strongest) in a bracket of `size` slots. Seed `i`
+/// meets seed `size - 1 - i` in the first round, so any byes go to the top seeds.
+pub fn seed_leaf(size: usize, seed: usize) -> usize {
+    let pair = seed.min(size - 1 - seed);
+    size - 1 + 2 * pair + usize::from(seed >= size / 2)
+}
+
+/// Moves every seed without a first-round opponent straight into the second round. Run once all
+/// seeds are in place.
+pub fn advance_byes(nodes: &mut [Pubkey]) {
+    let size = nodes.len().div_ceil(2);
+    for left in (size - 1..nodes.len()).step_by(2) {
+        if nodes[left + 1] == Pubkey::default() {
+            nodes[parent(left)] = nodes[left];
+        }
+    }
+}
+
+/// Builds the initial node array for `seeds`, strongest first, in one go.
+pub fn seed_bracket(seeds: &[Pubkey]) -> Result<Vec<Pubkey>> {
+    let size = bracket_size(seeds.len() as u32)? as usize;
+
+    let mut nodes = vec![Pubkey::default(); 2 * size - 1];
+    for (seed, player) in seeds.iter().enumerate() {
+        nodes[seed_leaf(size, seed)] = *player;
+    }
+    advance_byes(&mut nodes);
+
+    Ok(nodes)
+}
+
+/// Records `winner` as the result of the match deciding `node` and returns the loser.
+pub fn report_result(nodes: &mut [Pubkey], node: usize, winner: Pubkey) -> Result<Pubkey> {
+    let size = nodes.len().div_ceil(2);
+    require!(node < size - 1, ErrorCode::InvalidBracketNode);
+    require!(nodes[node] == Pubkey::default(), ErrorCode::MatchAlreadyReported);
+
+    let (left, right) = children(node);
+    require!(
+        nodes[left] != Pubkey::default() && nodes[right] != Pubkey::default(),
+        ErrorCode::BracketMatchNotReady
+    );
+
+    let loser = if winner == nodes[left] {
+        nodes[right]
+    } else if winner == nodes[right] {
+        nodes[left]
+    } else {
+        return err!(ErrorCode::WinnerNotInMatch);
+    };
+
+    nodes[node] = winner;
+    Ok(loser)
+}
+
+/// Final standings of a completed bracket: the champion, the runner-up, then the losers of each
+/// earlier round as a tied group.
+pub fn standings(nodes: &[Pubkey], positions: usize) -> Result<Vec<Winner>> {
+    require!(
+        nodes.first().is_some_and(|champion| *champion != Pubkey::default()),
+        ErrorCode::BracketIncomplete
+    );
+
+    let mut levels = vec![vec![nodes[0]]];
+    let mut round_start = 1;
+
+    while round_start < nodes.len() {
+        let round_end = 2 * round_start + 1;
+        levels.push(
+            (round_start..round_end)
+                .filter(|&node| nodes[node] != Pubkey::default() && nodes[node] != nodes[parent(node)])
+                .map(|node| nodes[node])
+                .collect(),
+        );
+        round_start = round_end;
+    }
+
+    Ok(placements(levels, positions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{keys, players};
+
+    #[test]
+    fn bracket_size_rounds_up_to_a_power_of_two() {
+        assert_eq!(bracket_size(2).unwrap(), 2);
+        assert_eq!(bracket_size(5).unwrap(), 8);
+        assert_eq!(bracket_size(MAX_BRACKET_PLAYERS).unwrap(), MAX_BRACKET_PLAYERS);
+        assert_eq!(bracket_size(1).unwrap_err(), ErrorCode::InvalidBracketSize.into());
+        assert_eq!(bracket_size(MAX_BRACKET_PLAYERS + 1).unwrap_err(), ErrorCode::InvalidBracketSize.into());
+    }
+
+    #[test]
+    fn top_seeds_receive_the_byes() {
+        let s = players(3);
+
+        let nodes = seed_bracket(&s).unwrap();
+
+        assert_eq!(nodes.len(), 7);
+        assert_eq!(nodes[3..], [s[0], Pubkey::default(), s[1], s[2]]);
+        // Seed 0 has a bye and already sits in the final
+        assert_eq!(nodes[1], s[0]);
+        assert_eq!(nodes[2], Pubkey::default());
+    }
+
+    #[test]
+    fn seed_leaves_pair_the_strongest_with_the_weakest() {
+        // Leaves 7..15 of an 8-slot bracket, two per first-round match
+        let leaves: Vec<_> = (0..8).map(|seed| seed_leaf(8, seed)).collect();
+
+        assert_eq!(leaves, vec![7, 9, 11, 13, 14, 12, 10, 8]);
+    }
+
+    #[test]
+    fn results_advance_winners_and_reject_bad_reports() {
+        let s = players(3);
+        let mut nodes = seed_bracket(&s).unwrap();
+
+        assert_eq!(report_result(&mut nodes, 0, s[0]).unwrap_err(), ErrorCode::BracketMatchNotReady.into());
+        assert_eq!(report_result(&mut nodes, 2, s[0]).unwrap_err(), ErrorCode::WinnerNotInMatch.into());
+        assert_eq!(report_result(&mut nodes, 3, s[0]).unwrap_err(), ErrorCode::InvalidBracketNode.into());
+
+        assert_eq!(report_result(&mut nodes, 2, s[2]).unwrap(), s[1]);
+        assert_eq!(report_result(&mut nodes, 2, s[2]).unwrap_err(), ErrorCode::MatchAlreadyReported.into());
+        assert_eq!(nodes[2], s[2]);
+    }
+
+    #[test]
+    fn standings_follow_the_rounds_players_went_out_in() {
+        let s = players(3);
+        let mut nodes = seed_bracket(&s).unwrap();
+        assert_eq!(standings(&nodes, 3).err(), Some(ErrorCode::BracketIncomplete.into()));
+
+        report_result(&mut nodes, 2, s[2]).unwrap();
+        report_result(&mut nodes, 0, s[0]).unwrap();
+
+        let winners: Vec<_> = standings(&nodes, 3).unwrap().iter().map(keys).collect();
+
+        assert_eq!(winners, vec![(vec![s[0]], 1), (vec![s[2]], 1), (vec![s[1]], 1)]);
+    }
+
+    #[test]
+    fn losers_of_the_same_round_share_the_places_left() {
+        let s = players(4);
+        let mut nodes = seed_bracket(&s).unwrap();
+        // Seeds 0 v 3 and 1 v 2 in the semifinals
+        report_result(&mut nodes, 1, s[0]).unwrap();
+        report_result(&mut nodes, 2, s[1]).unwrap();
+        report_result(&mut nodes, 0, s[1]).unwrap();
+
+        let winners: Vec<_> = standings(&nodes, 3).unwrap().iter().map(keys).collect();
+
+        assert_eq!(winners, vec![(vec![s[1]], 1), (vec![s[0]], 1), (vec![s[3], s[2]], 1)]);
+    }
+}
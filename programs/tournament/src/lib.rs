@@ -1,10 +1,35 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::solana_program::sysvar::slot_hashes;
+use anchor_spl::token_2022::spl_token_2022::extension::{
+    transfer_fee::TransferFeeConfig, BaseStateWithExtensions, StateWithExtensions,
+};
+use anchor_spl::token_2022_extensions::transfer_fee::{harvest_withheld_tokens_to_mint, HarvestWithheldTokensToMint};
+use anchor_spl::token_interface::{self, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked};
+
+pub mod bracket;
+pub mod payout;
+pub mod seating;
+pub mod swiss;
+#[cfg(test)]
+mod test_utils;
+
+use bracket::{advance_byes, bracket_size, report_result, seed_leaf, standings, MAX_BRACKET_PLAYERS};
+use payout::{
+    calculate_percentage_amount, calculate_total_buy_ins, plan_payouts, split_pro_rata, winner_keys, BasisPoints,
+    PayoutPrecision, TieRemainderPolicy,
+};
+use seating::{commitment_lapsed, seat_players, seating_commitment, seating_seed, slot_hash_after};
+use swiss::{record_score, SwissRecord, SwissResult, WIN_POINTS};
 
 declare_id!("BSA4cRmwYsbuCcRcmgSrhN51iBJgLBB5QdTK2kpqTDor");
 
-pub const PROGRAM_AUTHORITY: &str = "DCfE4QmioyzLxMFA1i95H2izi78FYE8aD4v2rwavzhiC";
+pub const MAX_ALLOWED_CREATORS: usize = 32;
 
-#[derive(Clone, Copy, PartialEq, AnchorSerialize, AnchorDeserialize)]
+/// Planned payouts that fit in the 1024 bytes of return data (4-byte length + 40 bytes each).
+pub const MAX_PREVIEW_PAYOUTS: usize = 25;
+
+#[derive(Clone, Copy, PartialEq, AnchorSerialize, AnchorDeserialize, InitSpace)]
 pub enum TournamentPhase {
     Registration, 
     Playing, 
@@ -12,16 +37,91 @@ pub enum TournamentPhase {
     Cancelled,
 }
 
+/// How final standings are decided: submitted by the authority, or derived on-chain.
+#[derive(Clone, Copy, PartialEq, AnchorSerialize, AnchorDeserialize, InitSpace)]
+pub enum TournamentFormat {
+    Standard,
+    SingleElimination,
+    Swiss,
+}
+
+#[event]
+pub struct ConfigInitialized {
+    pub config: Pubkey,
+    pub admin: Pubkey,
+    pub treasury: Pubkey,
+    pub max_operator_fee_percentage: u16,
+    pub min_tournament_prize_percentage: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AllowedCreatorAdded {
+    pub creator: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AllowedCreatorRemoved {
+    pub creator: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeeLimitsUpdated {
+    pub max_operator_fee_percentage: u16,
+    pub min_tournament_prize_percentage: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TreasuryUpdated {
+    pub old_treasury: Pubkey,
+    pub new_treasury: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdminTransferProposed {
+    pub current_admin: Pubkey,
+    pub pending_admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdminTransferAccepted {
+    pub previous_admin: Pubkey,
+    pub new_admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RegistryInitialized {
+    pub registry: Pubkey,
+    pub operator: Pubkey,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct TournamentCreated {
     pub tournament: Pubkey,
+    pub tournament_id: u64,
+    pub operator: Pubkey,
     pub buy_in_amount: u64,
-    pub max_players: u8,
+    pub max_players: u32,
+    pub min_players: u32,
+    pub registration_closes_at: i64,
+    pub unregister_penalty_percentage: u16,
+    pub max_play_duration: i64,
+    pub sweep_policy: SweepPolicy,
+    pub sweep_grace_period: i64,
+    pub tie_remainder_policy: TieRemainderPolicy,
     pub match_size: u8,
     pub tournament_prize_percentage: u16,
     pub match_prize_percentage: u16,
     pub operator_fee_percentage: u16,
     pub authority: Pubkey,
+    pub mint: Option<Pubkey>,
     pub timestamp: i64,
 }
 
@@ -30,16 +130,18 @@ pub struct PlayerBoughtIn {
     pub tournament: Pubkey,
     pub player: Pubkey,
     pub buy_in_amount: u64,
-    pub current_players: u8,
+    pub current_players: u32,
     pub timestamp: i64,
 }
 
 #[event]
 pub struct TournamentStarted {
     pub tournament: Pubkey,
-    pub current_players: u8,
-    pub payout_percentages: Vec<u16>,
-    pub match_payout_percentages: Vec<u16>,
+    pub current_players: u32,
+    pub format: TournamentFormat,
+    pub payout_percentages: Vec<u32>,
+    pub match_payout_percentages: Vec<u32>,
+    pub payout_precision: PayoutPrecision,
     pub timestamp: i64,
 }
 
@@ -48,13 +150,14 @@ pub struct TournamentFinalized {
     pub tournament: Pubkey,
     pub winners: Vec<Pubkey>,
     pub total_prize_pool: u128,
+    pub tie_remainder_policy: TieRemainderPolicy,
     pub timestamp: i64,
 }
 
 #[event]
 pub struct MatchRewardsDistributed {
     pub tournament: Pubkey,
-    pub match_id: u32,
+    pub match_id: [u8; 32],
     pub winners: Vec<Pubkey>,
     pub total_match_pool: u128,
     pub timestamp: i64,
@@ -82,826 +185,3687 @@ pub struct ParticipantRefunded {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct AuthorityTransferProposed {
+    pub tournament: Pubkey,
+    pub current_authority: Pubkey,
+    pub pending_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuthorityTransferAccepted {
+    pub tournament: Pubkey,
+    pub previous_authority: Pubkey,
+    pub new_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+/// Where `sweep_remainder` sends escrow funds nobody is owed once the grace period has passed.
+#[derive(Clone, Copy, PartialEq, AnchorSerialize, AnchorDeserialize, InitSpace)]
+pub enum SweepPolicy {
+    /// The tournament prize winners, in proportion to their prizes. Shares are recorded on their
+    /// entries and paid out by `claim_prize`; merkle settlements record no prizes on-chain, so
+    /// their remainder goes to the treasury.
+    Winners,
+    /// The current tournament authority.
+    Operator,
+    Treasury,
+}
+
+#[derive(Clone, Copy, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub enum PrizeSource {
+    Tournament,
+    Match,
+}
+
+#[event]
+pub struct PrizeRecorded {
+    pub tournament: Pubkey,
+    pub player: Pubkey,
+    pub amount: u64,
+    pub source: PrizeSource,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PrizeClaimed {
+    pub tournament: Pubkey,
+    pub player: Pubkey,
+    pub claimer: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TournamentFinalizedWithMerkleRoot {
+    pub tournament: Pubkey,
+    pub merkle_root: [u8; 32],
+    pub total_amount: u64,
+    pub leaf_count: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TournamentExpired {
+    pub tournament: Pubkey,
+    pub current_players: u32,
+    pub min_players: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PlayerUnregistered {
+    pub tournament: Pubkey,
+    pub player: Pubkey,
+    pub refund_amount: u64,
+    pub penalty_amount: u64,
+    pub current_players: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TournamentEmergencyCancelled {
+    pub tournament: Pubkey,
+    pub cancelled_by: Pubkey,
+    pub reason_code: u8,
+    pub timed_out: bool,
+    pub refund_per_player: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TournamentClosed {
+    pub tournament: Pubkey,
+    pub closed_by: Pubkey,
+    pub rent_recipient: Pubkey,
+    pub treasury: Pubkey,
+    pub swept_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RemainderSwept {
+    pub tournament: Pubkey,
+    pub policy: SweepPolicy,
+    pub recipient: Option<Pubkey>,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+/// A single payout computed by `preview_finalize` or `preview_match_rewards`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PlannedPayout {
+    pub player: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct MatchCreated {
+    pub tournament: Pubkey,
+    pub match_id: [u8; 32],
+    pub players: Vec<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BracketMatchReported {
+    pub tournament: Pubkey,
+    pub node_index: u32,
+    pub winner: Pubkey,
+    pub loser: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SwissPairingPublished {
+    pub tournament: Pubkey,
+    pub round: u16,
+    pub player_a: Pubkey,
+    pub player_b: Option<Pubkey>, // None for a bye
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SwissResultReported {
+    pub tournament: Pubkey,
+    pub round: u16,
+    pub player_a: Pubkey,
+    pub player_b: Pubkey,
+    pub result: SwissResult,
+    pub points_a: u32,
+    pub points_b: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SwissRoundOpened {
+    pub tournament: Pubkey,
+    pub round: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SeatingCommitted {
+    pub tournament: Pubkey,
+    pub commitment: [u8; 32],
+    pub slot: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MatchesSeeded {
+    pub tournament: Pubkey,
+    pub seed: [u8; 32],
+    pub slot: u64,
+    pub slot_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PlayersSeated {
+    pub tournament: Pubkey,
+    pub player_count: u32,
+    pub match_size: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EntryClosed {
+    pub tournament: Pubkey,
+    pub player: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RecordsClosed {
+    pub tournament: Pubkey,
+    pub record_count: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BracketSeeded {
+    pub tournament: Pubkey,
+    pub player_count: u32,
+    pub size: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SeatingCommitmentLapsed {
+    pub tournament: Pubkey,
+    pub commitment: [u8; 32],
+    pub slot: u64,
+    pub lapsed_count: u16,
+    pub timestamp: i64,
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize)]
 pub enum Winner {
     Individual(Pubkey),
     Group(Vec<Pubkey>, u8), // players, positions_consumed
 }
 
-fn is_participant(tournament_state: &TournamentState, player: &Pubkey) -> bool {
-    for participant in tournament_state.participants.iter() {
-        if participant == player {
-            return true;
-        }
+/// Loads the `PlayerEntry` for `player` from `accounts[index]`. Callers pass entries in the same
+/// order as the players they stand for, so membership is a single PDA address check. Entries are
+/// only ever created by `buy_in`, so a match proves the player bought into the tournament.
+fn player_entry_at<'a, 'info>(
+    accounts: &'a [AccountInfo<'info>],
+    index: usize,
+    tournament: &Pubkey,
+    player: &Pubkey,
+) -> Result<(&'a AccountInfo<'info>, PlayerEntry)> {
+    let account = accounts.get(index).ok_or(ErrorCode::MissingWinnerAccount)?;
+    let entry = read_player_entry(account, tournament).ok_or(ErrorCode::WinnerNotParticipant)?;
+    
+    let entry_address = Pubkey::create_program_address(
+        &[b"entry", tournament.as_ref(), player.as_ref(), &[entry.bump]],
+        &crate::ID,
+    ).map_err(|_| ErrorCode::WinnerNotParticipant)?;
+    require_keys_eq!(account.key(), entry_address, ErrorCode::WinnerNotParticipant);
+    
+    Ok((account, entry))
+}
+
+/// Reads the `PlayerEntry` stored in `account`, if it is one and belongs to `tournament`.
+fn read_player_entry(account: &AccountInfo, tournament: &Pubkey) -> Option<PlayerEntry> {
+    if *account.owner != crate::ID {
+        return None;
     }
-    false
+    let data = account.try_borrow_data().ok()?;
+    let entry = PlayerEntry::try_deserialize(&mut &data[..]).ok()?;
+    (entry.tournament == *tournament).then_some(entry)
 }
 
-fn calculate_total_buy_ins(current_players: u8, buy_in_amount: u64) -> Result<u128> {
-    let total = current_players as u128 * buy_in_amount as u128;
-    require!(
-        total / buy_in_amount as u128 == current_players as u128,
-        ErrorCode::CalculationOverflow
-    );
-    Ok(total)
+/// Closes a program-owned account that is not part of an Accounts struct, the way `close = ...`
+/// would: its rent goes to `destination` and the account is handed back to the system program.
+fn close_program_account<'info>(account: &AccountInfo<'info>, destination: &AccountInfo<'info>) -> Result<()> {
+    let rent = account.lamports();
+    **destination.try_borrow_mut_lamports()? = destination
+        .lamports()
+        .checked_add(rent)
+        .ok_or(ErrorCode::CalculationOverflow)?;
+    **account.try_borrow_mut_lamports()? = 0;
+    
+    account.assign(&System::id());
+    account.resize(0)?;
+    Ok(())
 }
 
-fn calculate_percentage_amount(total: u128, percentage: u16) -> Result<u128> {
-    let amount = (total * percentage as u128) / 10000;
-    require!(
-        amount <= total,
-        ErrorCode::CalculationOverflow
-    );
-    Ok(amount)
+/// Applies `update` to the `PlayerEntry` for `player` at `accounts[index]` and writes it back.
+fn update_player_entry<F>(accounts: &[AccountInfo], index: usize, tournament: &Pubkey, player: &Pubkey, update: F) -> Result<()>
+where
+    F: FnOnce(&mut PlayerEntry) -> Result<()>,
+{
+    let (account, mut entry) = player_entry_at(accounts, index, tournament, player)?;
+    require!(account.is_writable, ErrorCode::PlayerEntryNotWritable);
+    
+    update(&mut entry)?;
+    
+    let mut data = account.try_borrow_mut_data()?;
+    entry.try_serialize(&mut &mut data[..])?;
+    Ok(())
 }
 
-fn transfer_from_escrow<'info>(
-    escrow_pda: &AccountInfo<'info>,
-    destination: &AccountInfo<'info>,
-    amount: u64,
-    tournament_key: Pubkey,
-    escrow_bump: u8,
-    system_program: &AccountInfo<'info>,
-) -> Result<()> {
-    let seeds = &[b"escrow", tournament_key.as_ref(), &[escrow_bump]];
-    let signer = &[&seeds[..]];
+/// Records a prize on the winner's `PlayerEntry` so it can later be paid out by `claim_prize`.
+fn record_prize(accounts: &[AccountInfo], index: usize, tournament: &Pubkey, player: &Pubkey, amount: u64, source: PrizeSource) -> Result<()> {
+    update_player_entry(accounts, index, tournament, player, |entry| {
+        match source {
+            PrizeSource::Tournament => {
+                entry.prize_amount = entry.prize_amount.checked_add(amount).ok_or(ErrorCode::CalculationOverflow)?;
+            },
+            PrizeSource::Match => {
+                entry.match_rewards_amount = entry.match_rewards_amount.checked_add(amount).ok_or(ErrorCode::CalculationOverflow)?;
+            }
+        }
+        entry.claimed = false;
+        Ok(())
+    })?;
     
-    let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
-        &escrow_pda.key(),
-        &destination.key(),
+    emit!(PrizeRecorded {
+        tournament: *tournament,
+        player: *player,
         amount,
-    );
+        source,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
     
-    let account_infos = &[escrow_pda.clone(), destination.clone(), system_program.clone()];
-    anchor_lang::solana_program::program::invoke_signed(&transfer_ix, account_infos, signer)?;
     Ok(())
 }
 
-#[program]
-pub mod tournament {
-    use super::*;
-
-    pub fn initialize_tournament(
-        ctx: Context<InitializeTournament>,
-        buy_in_amount: u64,
-        max_players: u8,
-        match_size: u8,
-        tournament_prize_percentage: u16,
-        match_prize_percentage: u16,
-        operator_fee_percentage: u16,
-    ) -> Result<()> {
-        let tournament_state = &mut ctx.accounts.tournament_state;
-        
-        require!(buy_in_amount > 0, ErrorCode::InvalidBuyInAmount);
-        require!(max_players >= 2 && max_players <= 100, ErrorCode::InvalidMaxPlayers);
-        require!(match_size >= 2 && match_size <= max_players, ErrorCode::InvalidMatchSize);
-        require!(tournament_prize_percentage > 0, ErrorCode::InvalidTournamentPrizePercentage);
-        require!(operator_fee_percentage > 0 && operator_fee_percentage <= 1500, ErrorCode::InvalidOperatorFeePercentage);
-        
-        require!(
-            tournament_prize_percentage + match_prize_percentage + operator_fee_percentage == 10000,
-            ErrorCode::InvalidPercentages
-        );
-        
-        require!(
-            tournament_prize_percentage >= 5000, // At least 50% to tournament prizes
-            ErrorCode::TournamentPrizeTooLow
-        );
-        
-        require!(
-            operator_fee_percentage <= 1500, // Max 15% operator fee
-            ErrorCode::OperatorFeeTooHigh
-        );
+/// Records a planned list of payouts on the winners' entries, which `accounts` carries in payout
+/// order, returning the total recorded.
+fn record_payouts(accounts: &[AccountInfo], tournament: &Pubkey, payouts: &[(Pubkey, u64)], source: PrizeSource) -> Result<u128> {
+    let mut total_recorded = 0u128;
+    
+    for (position, (player, amount)) in payouts.iter().enumerate() {
+        msg!("Recording {} lamports for payout #{} ({})", amount, position + 1, player);
         
-        tournament_state.buy_in_amount = buy_in_amount;
-        tournament_state.max_players = max_players;
-        tournament_state.current_players = 0;
-        tournament_state.escrow_bump = ctx.bumps.escrow_pda;
-        tournament_state.match_size = match_size;
-        tournament_state.phase = TournamentPhase::Registration;
-        tournament_state.participants = Vec::new();
-        tournament_state.paid_match_ids = Vec::new();
-        tournament_state.tournament_prize_percentage = tournament_prize_percentage;
-        tournament_state.match_prize_percentage = match_prize_percentage;
-        tournament_state.operator_fee_percentage = operator_fee_percentage;
-        tournament_state.tournament_payouts = Vec::new();
-        tournament_state.match_payout_percentages = Vec::new();
-        tournament_state.operator_fee_withdrawn = false;
-        tournament_state.refunded_participants = Vec::new();
+        record_prize(accounts, position, tournament, player, *amount, source)?;
+        total_recorded += *amount as u128;
+    }
+    
+    Ok(total_recorded)
+}
 
-        tournament_state.authority = ctx.accounts.payer.key();
+/// Records `remainder` on the tournament prize winners' entries, which `accounts` carries ordered
+/// by player, in proportion to the prizes recorded on them. `prizes_total` is everything recorded
+/// so far, so leaving a winner out cannot go unnoticed.
+fn record_remainder_shares(accounts: &[AccountInfo], tournament: &Pubkey, prizes_total: u64, remainder: u64) -> Result<()> {
+    let mut winners: Vec<(Pubkey, u64)> = Vec::with_capacity(accounts.len());
     
-        msg!("Tournament initialized with buy-in: {}, max players: {}, match size: {}", 
-             buy_in_amount, max_players, match_size);
-        msg!("Prize distribution: Tournament {}%, Match {}%, Operator {}%",
-             tournament_prize_percentage / 100, match_prize_percentage / 100, operator_fee_percentage / 100);
-        msg!("Escrow PDA: {}", ctx.accounts.escrow_pda.key());
-        msg!("Authority: {}", tournament_state.authority);
+    for account in accounts.iter() {
+        let entry = read_player_entry(account, tournament).ok_or(ErrorCode::WinnerNotParticipant)?;
+        require!(entry.prize_amount > 0, ErrorCode::InvalidWinner);
         
-        emit!(TournamentCreated {
-            tournament: tournament_state.key(),
-            buy_in_amount,
-            max_players,
-            match_size,
-            tournament_prize_percentage,
-            match_prize_percentage,
-            operator_fee_percentage,
-            authority: tournament_state.authority,
-            timestamp: Clock::get()?.unix_timestamp,
-        });
-        
-        Ok(())
+        // Strictly increasing keys rule out passing the same winner twice
+        if let Some((previous, _)) = winners.last() {
+            require!(*previous < entry.player, ErrorCode::DuplicateWinner);
+        }
+        winners.push((entry.player, entry.prize_amount));
+    }
+    
+    let total_prizes: u128 = winners.iter().map(|(_, prize)| *prize as u128).sum();
+    require!(total_prizes == prizes_total as u128, ErrorCode::IncompleteWinnerEntries);
+    
+    let weights: Vec<u64> = winners.iter().map(|(_, prize)| *prize).collect();
+    let shares = split_pro_rata(remainder, &weights)?;
+    
+    for (index, ((player, _), share)) in winners.iter().zip(shares).enumerate() {
+        if share > 0 {
+            record_prize(accounts, index, tournament, player, share, PrizeSource::Tournament)?;
+        }
+    }
+    
+    Ok(())
+}
+
+/// Final standings to settle: the submitted list for standard tournaments, the standings of the
+/// completed bracket for single-elimination ones, or the accumulated scores for Swiss ones.
+/// Submitting winners is only allowed for standard tournaments.
+fn settlement_winners(
+    tournament_state: &TournamentState,
+    bracket: &Option<Account<Bracket>>,
+    swiss_standings: &Option<Account<SwissStandings>>,
+    winners: Vec<Winner>,
+) -> Result<Vec<Winner>> {
+    match tournament_state.format {
+        TournamentFormat::Standard => Ok(winners),
+        TournamentFormat::SingleElimination => {
+            require!(winners.is_empty(), ErrorCode::WinnersFromStandings);
+            let bracket = bracket.as_ref().ok_or(ErrorCode::MissingBracket)?;
+            standings(&bracket.nodes, tournament_state.tournament_payouts.len())
+        },
+        TournamentFormat::Swiss => {
+            require!(winners.is_empty(), ErrorCode::WinnersFromStandings);
+            require!(tournament_state.pending_results == 0, ErrorCode::RoundResultsPending);
+            
+            // Every player must have been paired, otherwise an unranked entry could hide a strong score
+            let swiss_standings = swiss_standings.as_ref().ok_or(ErrorCode::MissingStandings)?;
+            require!(
+                swiss_standings.records.len() == tournament_state.current_players as usize,
+                ErrorCode::IncompleteStandings
+            );
+            
+            Ok(swiss::standings(swiss_standings.records.clone(), tournament_state.tournament_payouts.len()))
+        }
     }
+}
 
-    pub fn buy_in(ctx: Context<BuyIn>) -> Result<()> {
-        let tournament_state = &mut ctx.accounts.tournament_state;
-        
-        require!(
-            tournament_state.phase == TournamentPhase::Registration, 
-            ErrorCode::InvalidPhase
-        );
-        
+/// Validates a `finalize_tournament` call against the current state and plans its payouts
+/// without recording anything, so the instruction and its preview always agree.
+fn plan_tournament_settlement(tournament_state: &Account<TournamentState>, accounts: &[AccountInfo], winners: &[Winner]) -> Result<Vec<(Pubkey, u64)>> {
+    require!(
+        tournament_state.phase == TournamentPhase::Playing,
+        ErrorCode::InvalidPhase
+    );
+    
+    require!(
+        !winners.is_empty(),
+        ErrorCode::InvalidWinnerCount
+    );
+    
+    // Validate all winners are participants; their entries are passed in winner order
+    for (index, player) in winner_keys(winners).iter().enumerate() {
+        player_entry_at(accounts, index, &tournament_state.key(), player)?;
+    }
+    
+    let tournament_pool = calculate_tournament_pool(tournament_state)?;
+    
+    plan_payouts(tournament_pool, &tournament_state.tournament_payouts, tournament_state.payout_precision, tournament_state.tie_remainder_policy, winners)
+}
+
+/// Validates a `distribute_match_rewards` call against the current state and plans its payouts
+/// without recording anything, so the instruction and its preview always agree.
+fn plan_match_settlement(tournament_state: &Account<TournamentState>, match_state: &MatchState, accounts: &[AccountInfo], winners: &[Winner]) -> Result<Vec<(Pubkey, u64)>> {
+    // Matches can be paid as soon as they finish, not just once the whole tournament is over
+    require!(
+        tournament_state.phase == TournamentPhase::Playing || tournament_state.phase == TournamentPhase::Finalized,
+        ErrorCode::InvalidPhase
+    );
+    
+    require!(
+        tournament_state.match_prize_percentage > 0,
+        ErrorCode::InvalidMatchPrizePercentage
+    );
+    
+    // Once the grace period has passed the unpaid match pool belongs to sweep_remainder
+    if tournament_state.phase == TournamentPhase::Finalized {
+        let sweep_opens_at = tournament_state
+            .finalized_at
+            .checked_add(tournament_state.sweep_grace_period)
+            .ok_or(ErrorCode::CalculationOverflow)?;
         require!(
-            tournament_state.current_players < tournament_state.max_players,
-            ErrorCode::TournamentFull
+            Clock::get()?.unix_timestamp < sweep_opens_at,
+            ErrorCode::SweepGracePeriodElapsed
         );
-        
+    }
+    
+    require!(
+        !winners.is_empty(),
+        ErrorCode::InvalidWinnerCount
+    );
+    
+    // Winner entries are passed in winner order
+    for (index, player) in winner_keys(winners).iter().enumerate() {
         require!(
-            tournament_state.current_players < u8::MAX,
-            ErrorCode::PlayerCountOverflow
+            player != &Pubkey::default(),
+            ErrorCode::InvalidWinner
         );
-        
-        for participant in tournament_state.participants.iter() {
-            require!(
-                *participant != ctx.accounts.player.key(),
-                ErrorCode::AlreadyRegistered
-            );
-        }
-        
-        let cpi_context = CpiContext::new(
-            ctx.accounts.system_program.to_account_info(),
-            anchor_lang::system_program::Transfer {
-                from: ctx.accounts.player.to_account_info(),
-                to: ctx.accounts.escrow_pda.to_account_info(),
+        require!(match_state.players.contains(player), ErrorCode::WinnerNotInMatch);
+        player_entry_at(accounts, index, &tournament_state.key(), player)?;
+    }
+    
+    let total_buy_ins = calculate_total_buy_ins(tournament_state.current_players, tournament_state.net_buy_in_amount)?;
+    let total_match_pool = calculate_percentage_amount(total_buy_ins, BasisPoints::new(tournament_state.match_prize_percentage)?)?;
+    
+    let num_matches = (tournament_state.current_players as u128 + tournament_state.match_size as u128 - 1) / tournament_state.match_size as u128;
+    
+    require!(
+        num_matches > 0,
+        ErrorCode::InvalidMatchCount
+    );
+    
+    let match_pool = total_match_pool / num_matches;
+    
+    require!(
+        match_pool > 0,
+        ErrorCode::NoMatchRewards
+    );
+    
+    require!(
+        match_pool * num_matches <= total_match_pool,
+        ErrorCode::CalculationOverflow
+    );
+    
+    let payouts = plan_payouts(match_pool, &tournament_state.match_payout_percentages, tournament_state.payout_precision, tournament_state.tie_remainder_policy, winners)?;
+    
+    require!(
+        payouts.iter().all(|(_, amount)| *amount > 0),
+        ErrorCode::NoMatchRewards
+    );
+    
+    // Match rewards recorded so far, in any phase, may never exceed the match share set at creation
+    let match_rewards_after = payouts
+        .iter()
+        .try_fold(tournament_state.match_rewards_recorded as u128, |total, (_, amount)| total.checked_add(*amount as u128))
+        .ok_or(ErrorCode::CalculationOverflow)?;
+    require!(
+        match_rewards_after <= total_match_pool,
+        ErrorCode::MatchPoolExhausted
+    );
+    
+    Ok(payouts)
+}
+
+/// Converts planned payouts into return data, which the runtime caps at 1024 bytes.
+fn preview_payouts(payouts: Vec<(Pubkey, u64)>) -> Result<Vec<PlannedPayout>> {
+    require!(payouts.len() <= MAX_PREVIEW_PAYOUTS, ErrorCode::PreviewTooLarge);
+    
+    Ok(payouts
+        .into_iter()
+        .map(|(player, amount)| PlannedPayout { player, amount })
+        .collect())
+}
+
+/// Hashes a `(index, player, amount)` settlement leaf. Leaves and nodes use distinct prefixes so
+/// an internal node can never be presented as a leaf.
+fn merkle_leaf(index: u32, player: &Pubkey, amount: u64) -> [u8; 32] {
+    hashv(&[&[0u8], &index.to_le_bytes(), player.as_ref(), &amount.to_le_bytes()]).to_bytes()
+}
+
+/// Verifies a proof built with sorted sibling pairs against `root`.
+fn verify_merkle_proof(proof: &[[u8; 32]], root: &[u8; 32], leaf: [u8; 32]) -> bool {
+    let mut computed = leaf;
+    for sibling in proof.iter() {
+        computed = if computed <= *sibling {
+            hashv(&[&[1u8], &computed, sibling]).to_bytes()
+        } else {
+            hashv(&[&[1u8], sibling, &computed]).to_bytes()
+        };
+    }
+    computed == *root
+}
+
+fn validate_fee_limits(max_operator_fee_percentage: u16, min_tournament_prize_percentage: u16) -> Result<()> {
+    require!(
+        max_operator_fee_percentage > 0
+            && min_tournament_prize_percentage > 0
+            && max_operator_fee_percentage as u32 + min_tournament_prize_percentage as u32 <= 10000,
+        ErrorCode::InvalidFeeLimits
+    );
+    Ok(())
+}
+
+/// Per-player refund on every cancellation path: everything still owed out of the escrow (buy-ins
+/// plus unregister penalties, minus the operator fee and match rewards already paid) split evenly.
+fn calculate_cancellation_refund(tournament_state: &TournamentState) -> Result<u64> {
+    let total_buy_ins = calculate_total_buy_ins(tournament_state.current_players, tournament_state.net_buy_in_amount)?;
+    let operator_fee_paid = if tournament_state.operator_fee_withdrawn {
+        calculate_percentage_amount(total_buy_ins, BasisPoints::new(tournament_state.operator_fee_percentage)?)?
+    } else {
+        0
+    };
+    
+    let refundable = total_buy_ins
+        .checked_add(tournament_state.forfeited_amount as u128)
+        .and_then(|total| total.checked_sub(operator_fee_paid))
+        .and_then(|total| total.checked_sub(tournament_state.match_rewards_recorded as u128))
+        .ok_or(ErrorCode::CalculationOverflow)?;
+    
+    // With nobody left to refund, any penalties stay in the escrow and are swept at close
+    if tournament_state.current_players == 0 {
+        return Ok(0);
+    }
+    
+    Ok((refundable / tournament_state.current_players as u128) as u64)
+}
+
+/// Amount the escrow still owes to winners: recorded but unclaimed prizes and match rewards,
+/// plus the operator fee of a finalized tournament if it has not been withdrawn yet.
+fn calculate_outstanding_obligations(tournament_state: &TournamentState) -> Result<u128> {
+    let operator_fee_owed = if tournament_state.operator_fee_withdrawn || tournament_state.phase != TournamentPhase::Finalized {
+        0
+    } else {
+        let total_buy_ins = calculate_total_buy_ins(tournament_state.current_players, tournament_state.net_buy_in_amount)?;
+        calculate_percentage_amount(total_buy_ins, BasisPoints::new(tournament_state.operator_fee_percentage)?)?
+    };
+    
+    let tournament_owed = (tournament_state.prizes_recorded as u128
+        + tournament_state.merkle_total as u128
+        + tournament_state.remainder_recorded as u128)
+        .checked_sub(tournament_state.tournament_paid as u128)
+        .ok_or(ErrorCode::CalculationOverflow)?;
+    let match_owed = (tournament_state.match_rewards_recorded as u128)
+        .checked_sub(tournament_state.match_paid as u128)
+        .ok_or(ErrorCode::CalculationOverflow)?;
+    
+    Ok(tournament_owed + match_owed + operator_fee_owed)
+}
+
+/// Hard limits on what each payout bucket may draw from the escrow, checked after every outflow
+/// so that a rounding or percentage bug can never pay out more than was deposited.
+fn check_escrow_invariants(tournament_state: &TournamentState) -> Result<()> {
+    let total_buy_ins = calculate_total_buy_ins(tournament_state.current_players, tournament_state.net_buy_in_amount)?;
+    // Players who unregistered are gone from current_players, but their refunds came out of deposits too
+    let deposits = total_buy_ins
+        .checked_add(tournament_state.forfeited_amount as u128)
+        .and_then(|total| total.checked_add(tournament_state.unregister_refunds_paid as u128))
+        .ok_or(ErrorCode::CalculationOverflow)?;
+    
+    // Remainder shares recorded for winners are paid through claim_prize on top of the pool
+    let tournament_cap = calculate_tournament_pool(tournament_state)?
+        .checked_add(tournament_state.remainder_recorded as u128)
+        .ok_or(ErrorCode::CalculationOverflow)?;
+    require!(
+        tournament_state.tournament_paid as u128 <= tournament_cap,
+        ErrorCode::TournamentPayoutsExceeded
+    );
+    require!(
+        tournament_state.match_paid as u128 <= calculate_percentage_amount(total_buy_ins, BasisPoints::new(tournament_state.match_prize_percentage)?)?,
+        ErrorCode::MatchPayoutsExceeded
+    );
+    require!(
+        tournament_state.operator_paid as u128 <= calculate_percentage_amount(total_buy_ins, BasisPoints::new(tournament_state.operator_fee_percentage)?)?,
+        ErrorCode::OperatorPayoutsExceeded
+    );
+    require!(
+        tournament_state.refunds_paid as u128 <= deposits,
+        ErrorCode::RefundPayoutsExceeded
+    );
+    
+    let total_paid = tournament_state.tournament_paid as u128
+        + tournament_state.match_paid as u128
+        + tournament_state.operator_paid as u128
+        + tournament_state.refunds_paid as u128;
+    require!(total_paid <= deposits, ErrorCode::EscrowOverdrawn);
+    
+    Ok(())
+}
+
+/// Tournament prize pool: its share of the buy-ins plus any unregistration penalties.
+fn calculate_tournament_pool(tournament_state: &TournamentState) -> Result<u128> {
+    let total_buy_ins = calculate_total_buy_ins(tournament_state.current_players, tournament_state.net_buy_in_amount)?;
+    let share = calculate_percentage_amount(total_buy_ins, BasisPoints::new(tournament_state.tournament_prize_percentage)?)?;
+    share
+        .checked_add(tournament_state.forfeited_amount as u128)
+        .ok_or(ErrorCode::CalculationOverflow.into())
+}
+
+/// Token accounts backing the escrow of a token-denominated tournament.
+struct EscrowToken<'a, 'info> {
+    account: &'a InterfaceAccount<'info, TokenAccount>,
+    mint: &'a InterfaceAccount<'info, Mint>,
+    token_program: &'a Interface<'info, TokenInterface>,
+}
+
+/// Resolves the escrow token accounts for a tournament, returning `None` for native SOL
+/// tournaments and failing if a token tournament was called without its accounts.
+fn escrow_token_accounts<'a, 'info>(
+    tournament_state: &TournamentState,
+    escrow_token_account: &'a Option<InterfaceAccount<'info, TokenAccount>>,
+    mint: &'a Option<InterfaceAccount<'info, Mint>>,
+    token_program: &'a Option<Interface<'info, TokenInterface>>,
+) -> Result<Option<EscrowToken<'a, 'info>>> {
+    let Some(tournament_mint) = tournament_state.mint else {
+        return Ok(None);
+    };
+
+    let (Some(account), Some(mint), Some(token_program)) = (escrow_token_account, mint, token_program) else {
+        return err!(ErrorCode::MissingTokenAccounts);
+    };
+
+    require_keys_eq!(mint.key(), tournament_mint, ErrorCode::InvalidMint);
+    require_keys_eq!(account.mint, tournament_mint, ErrorCode::InvalidMint);
+    require_keys_eq!(*mint.to_account_info().owner, token_program.key(), ErrorCode::InvalidMint);
+
+    Ok(Some(EscrowToken { account, mint, token_program }))
+}
+
+/// Returns the account that should receive funds on behalf of `wallet`: the wallet itself for
+/// native SOL tournaments, or its token account for the tournament mint otherwise.
+fn payout_destination<'info>(
+    tournament_state: &TournamentState,
+    wallet: &AccountInfo<'info>,
+    token_account: &Option<InterfaceAccount<'info, TokenAccount>>,
+) -> Result<AccountInfo<'info>> {
+    let Some(tournament_mint) = tournament_state.mint else {
+        return Ok(wallet.clone());
+    };
+
+    let Some(token_account) = token_account else {
+        return err!(ErrorCode::MissingTokenAccounts);
+    };
+
+    require_keys_eq!(token_account.owner, wallet.key(), ErrorCode::InvalidTokenAccount);
+    require_keys_eq!(token_account.mint, tournament_mint, ErrorCode::InvalidMint);
+
+    Ok(token_account.to_account_info())
+}
+
+/// Amount the escrow is guaranteed to receive for `amount` sent through `mint`, assuming the
+/// higher of the two transfer fees configured on a Token-2022 mint.
+fn net_amount_after_transfer_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    if *mint.owner != anchor_spl::token_2022::ID {
+        return Ok(amount);
+    }
+
+    let data = mint.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<anchor_spl::token_2022::spl_token_2022::state::Mint>::unpack(&data)?;
+
+    let Ok(fee_config) = mint_state.get_extension::<TransferFeeConfig>() else {
+        return Ok(amount);
+    };
+
+    let older_fee = fee_config.older_transfer_fee.calculate_fee(amount).ok_or(ErrorCode::CalculationOverflow)?;
+    let newer_fee = fee_config.newer_transfer_fee.calculate_fee(amount).ok_or(ErrorCode::CalculationOverflow)?;
+
+    amount.checked_sub(older_fee.max(newer_fee)).ok_or(ErrorCode::CalculationOverflow.into())
+}
+
+/// Whether `mint` is a Token-2022 mint with the transfer fee extension, in which case token
+/// accounts may hold withheld fees that must be harvested before they can be closed.
+fn has_transfer_fee(mint: &AccountInfo) -> Result<bool> {
+    if *mint.owner != anchor_spl::token_2022::ID {
+        return Ok(false);
+    }
+
+    let data = mint.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<anchor_spl::token_2022::spl_token_2022::state::Mint>::unpack(&data)?;
+
+    Ok(mint_state.get_extension::<TransferFeeConfig>().is_ok())
+}
+
+fn transfer_from_escrow<'info>(
+    escrow_pda: &AccountInfo<'info>,
+    escrow_token: Option<&EscrowToken<'_, 'info>>,
+    destination: &AccountInfo<'info>,
+    amount: u64,
+    tournament_key: Pubkey,
+    escrow_bump: u8,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let seeds = &[b"escrow", tournament_key.as_ref(), &[escrow_bump]];
+    let signer = &[&seeds[..]];
+    
+    if let Some(escrow_token) = escrow_token {
+        let cpi_context = CpiContext::new_with_signer(
+            escrow_token.token_program.to_account_info(),
+            TransferChecked {
+                from: escrow_token.account.to_account_info(),
+                mint: escrow_token.mint.to_account_info(),
+                to: destination.clone(),
+                authority: escrow_pda.clone(),
             },
+            signer,
         );
+
+        return token_interface::transfer_checked(cpi_context, amount, escrow_token.mint.decimals);
+    }
+    
+    // The escrow may only dip below the rent-exempt minimum when it is being emptied entirely
+    let remaining = escrow_pda
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(ErrorCode::EscrowBelowRentExempt)?;
+    require!(
+        remaining == 0 || remaining >= Rent::get()?.minimum_balance(0),
+        ErrorCode::EscrowBelowRentExempt
+    );
+    
+    let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+        &escrow_pda.key(),
+        &destination.key(),
+        amount,
+    );
+    
+    let account_infos = &[escrow_pda.clone(), destination.clone(), system_program.clone()];
+    anchor_lang::solana_program::program::invoke_signed(&transfer_ix, account_infos, signer)?;
+    Ok(())
+}
+
+#[program]
+pub mod tournament {
+    use super::*;
+
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        max_operator_fee_percentage: u16,
+        min_tournament_prize_percentage: u16,
+        treasury: Pubkey,
+    ) -> Result<()> {
+        validate_fee_limits(max_operator_fee_percentage, min_tournament_prize_percentage)?;
         
-        anchor_lang::system_program::transfer(
-            cpi_context,
-            tournament_state.buy_in_amount,
-        )?;
-        
-        tournament_state.participants.push(ctx.accounts.player.key());
+        let config = &mut ctx.accounts.config;
         
-        tournament_state.current_players += 1;
+        config.admin = ctx.accounts.admin.key();
+        config.pending_admin = None;
+        config.allowed_creators = Vec::new();
+        config.max_operator_fee_percentage = max_operator_fee_percentage;
+        config.min_tournament_prize_percentage = min_tournament_prize_percentage;
+        config.bump = ctx.bumps.config;
+        config.treasury = treasury;
         
-        msg!("Player {} bought in with {} lamports", 
-             ctx.accounts.player.key(), 
-             tournament_state.buy_in_amount);
+        msg!("Global config initialized with admin {}", config.admin);
         
-        emit!(PlayerBoughtIn {
-            tournament: tournament_state.key(),
-            player: ctx.accounts.player.key(),
-            buy_in_amount: tournament_state.buy_in_amount,
-            current_players: tournament_state.current_players,
+        emit!(ConfigInitialized {
+            config: config.key(),
+            admin: config.admin,
+            treasury,
+            max_operator_fee_percentage,
+            min_tournament_prize_percentage,
             timestamp: Clock::get()?.unix_timestamp,
         });
         
         Ok(())
     }
 
-    pub fn start_tournament(
-        ctx: Context<StartTournament>,
-        payout_percentages: Vec<u16>,
-        match_payout_percentages: Vec<u16>,
-    ) -> Result<()> {
-        let tournament_state = &mut ctx.accounts.tournament_state;
-        
-        require!(
-            tournament_state.phase == TournamentPhase::Registration,
-            ErrorCode::InvalidPhase
-        );
-        
-        require!(
-            tournament_state.current_players > 0,
-            ErrorCode::NotEnoughPlayers
-        );
-        
-        require!(
-            !payout_percentages.is_empty() && payout_percentages.len() <= 20,
-            ErrorCode::InvalidPayoutCount
-        );
+    pub fn add_allowed_creator(ctx: Context<UpdateConfig>, creator: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
         
         require!(
-            payout_percentages.len() <= tournament_state.current_players as usize,
-            ErrorCode::TooManyPayoutPositions
+            !config.allowed_creators.contains(&creator),
+            ErrorCode::CreatorAlreadyAllowed
         );
         
-        let total_percentage: u32 = payout_percentages.iter().map(|&p| p as u32).sum();
         require!(
-            total_percentage == 10000,
-            ErrorCode::InvalidPercentages
+            config.allowed_creators.len() < MAX_ALLOWED_CREATORS,
+            ErrorCode::TooManyCreators
         );
         
-        // Validate individual payout percentages
-        for (_i, &percentage) in payout_percentages.iter().enumerate() {
-            require!(
-                percentage > 0,
-                ErrorCode::InvalidPayoutPercentage
-            );
-            require!(
-                percentage <= 10000,
-                ErrorCode::InvalidPayoutPercentage
-            );
-        }
+        config.allowed_creators.push(creator);
         
-        // Validate match payout percentages
-        require!(
-            !match_payout_percentages.is_empty() && match_payout_percentages.len() <= 8,
-            ErrorCode::InvalidMatchPayoutCount
-        );
+        msg!("Creator {} allowed to create tournaments", creator);
         
-        let match_total_percentage: u32 = match_payout_percentages.iter().map(|&p| p as u32).sum();
-        require!(
-            match_total_percentage == 10000,
-            ErrorCode::InvalidMatchPayoutPercentages
-        );
+        emit!(AllowedCreatorAdded {
+            creator,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
         
-        // Validate individual match payout percentages
-        for (_i, &percentage) in match_payout_percentages.iter().enumerate() {
-            require!(
-                percentage > 0,
-                ErrorCode::InvalidMatchPayoutPercentage
-            );
-            require!(
-                percentage <= 10000,
-                ErrorCode::InvalidMatchPayoutPercentage
-            );
-        }
+        Ok(())
+    }
+
+    pub fn remove_allowed_creator(ctx: Context<UpdateConfig>, creator: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
         
-        tournament_state.tournament_payouts = payout_percentages;
-        tournament_state.match_payout_percentages = match_payout_percentages;
+        let index = config
+            .allowed_creators
+            .iter()
+            .position(|allowed| *allowed == creator)
+            .ok_or(ErrorCode::CreatorNotAllowed)?;
         
-        tournament_state.phase = TournamentPhase::Playing;
+        config.allowed_creators.swap_remove(index);
         
-        msg!("Tournament started with {} players and {} payout positions", 
-             tournament_state.current_players, tournament_state.tournament_payouts.len());
-        msg!("Match payouts: {} positions with {}% total", 
-             tournament_state.match_payout_percentages.len(), match_total_percentage / 100);
+        msg!("Creator {} removed from allowed creators", creator);
         
-        emit!(TournamentStarted {
-            tournament: tournament_state.key(),
-            current_players: tournament_state.current_players,
-            payout_percentages: tournament_state.tournament_payouts.clone(),
-            match_payout_percentages: tournament_state.match_payout_percentages.clone(),
+        emit!(AllowedCreatorRemoved {
+            creator,
             timestamp: Clock::get()?.unix_timestamp,
         });
         
         Ok(())
     }
 
-    pub fn finalize_tournament<'a, 'b, 'c, 'info>(
-        ctx: Context<'a, 'b, 'c, 'info, FinalizeTournament<'info>>,
-        winners: Vec<Winner>
+    pub fn update_fee_limits(
+        ctx: Context<UpdateConfig>,
+        max_operator_fee_percentage: u16,
+        min_tournament_prize_percentage: u16,
     ) -> Result<()> {
-        let tournament_state = &mut ctx.accounts.tournament_state;
+        validate_fee_limits(max_operator_fee_percentage, min_tournament_prize_percentage)?;
         
-        require!(
-            tournament_state.phase == TournamentPhase::Playing,
-            ErrorCode::InvalidPhase
-        );
+        let config = &mut ctx.accounts.config;
         
-        require!(
-            !winners.is_empty(),
-            ErrorCode::InvalidWinnerCount
-        );
+        config.max_operator_fee_percentage = max_operator_fee_percentage;
+        config.min_tournament_prize_percentage = min_tournament_prize_percentage;
         
-        // Validate all winners are participants
-        for winner in winners.iter() {
-            match winner {
-                Winner::Individual(player) => {
-                    require!(is_participant(tournament_state, player), ErrorCode::WinnerNotParticipant);
-                },
-                Winner::Group(players, _) => {
-                    for player in players.iter() {
-                        require!(is_participant(tournament_state, player), ErrorCode::WinnerNotParticipant);
-                    }
-                }
-            }
-        }
+        msg!("Fee limits updated: max operator fee {}%, min tournament prize {}%",
+             max_operator_fee_percentage / 100, min_tournament_prize_percentage / 100);
         
-        let total_buy_ins = calculate_total_buy_ins(tournament_state.current_players, tournament_state.buy_in_amount)?;
-        let tournament_pool = calculate_percentage_amount(total_buy_ins, tournament_state.tournament_prize_percentage)?;
+        emit!(FeeLimitsUpdated {
+            max_operator_fee_percentage,
+            min_tournament_prize_percentage,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
         
-        let escrow_bump = tournament_state.escrow_bump;
-        let tournament_key = tournament_state.key();
+        Ok(())
+    }
+
+    pub fn update_treasury(ctx: Context<UpdateConfig>, new_treasury: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
         
-        // Track position counter for payout percentages
-        let mut position_counter = 0;
-        let mut total_distributed = 0u128;
-        
-        for (winner_index, winner) in winners.iter().enumerate() {
-            match winner {
-                Winner::Individual(player) => {
-                    // Single winner - direct payout
-                    if position_counter < tournament_state.tournament_payouts.len() {
-                        let amount = calculate_percentage_amount(tournament_pool, tournament_state.tournament_payouts[position_counter])?;
-                        
-                        // Find player account in remaining_accounts
-                        let mut player_account_found = false;
-                        for account in ctx.remaining_accounts.iter() {
-                            if account.key() == *player && !player_account_found {
-                                msg!("Transferring {} lamports to tournament winner #{} ({})", 
-                                     amount, position_counter + 1, player);
-                                
-                                transfer_from_escrow(
-                                    &ctx.accounts.escrow_pda.to_account_info(),
-                                    &account.to_account_info(),
-                                    amount as u64,
-                                    tournament_key,
-                                    escrow_bump,
-                                    &ctx.accounts.system_program.to_account_info(),
-                                )?;
-                                
-                                player_account_found = true;
-                                break;
-                            }
-                        }
-                        
-                        require!(player_account_found, ErrorCode::MissingWinnerAccount);
-                        total_distributed += amount;
-                    }
-                    position_counter += 1;
-                },
-                Winner::Group(players, positions_consumed) => {
-                    // Group of tied winners - combined prize pool
-                    require!(
-                        !players.is_empty(),
-                        ErrorCode::InvalidWinnerCount
-                    );
-                    
-                    require!(
-                        *positions_consumed > 0,
-                        ErrorCode::InvalidWinnerCount
-                    );
-                    
-                    // Calculate combined prize pool for consumed positions
-                    let mut combined_pool_percentage = 0u32;
-                    for i in position_counter..(position_counter + *positions_consumed as usize) {
-                        if i < tournament_state.tournament_payouts.len() {
-                            combined_pool_percentage += tournament_state.tournament_payouts[i] as u32;
-                        }
-                    }
-                    
-                    let combined_pool_amount = calculate_percentage_amount(tournament_pool, combined_pool_percentage as u16)?;
-                    
-                    let payout_per_player = combined_pool_amount / players.len() as u128;
-                    
-                    require!(
-                        payout_per_player * players.len() as u128 <= combined_pool_amount,
-                        ErrorCode::CalculationOverflow
-                    );
-                    
-                    let mut remaining_amount = combined_pool_amount;
-                    for (player_index, player) in players.iter().enumerate() {
-                        let amount_to_transfer = if player_index == players.len() - 1 {
-                            remaining_amount
-                        } else {
-                            payout_per_player
-                        };
-                        
-                        require!(
-                            amount_to_transfer > 0,
-                            ErrorCode::NoMatchRewards
-                        );
-                        
-                        let mut player_account_found = false;
-                        for account in ctx.remaining_accounts.iter() {
-                            if account.key() == *player && !player_account_found {
-                                msg!("Transferring {} lamports to tied winner group {} player {} ({})", 
-                                     amount_to_transfer, winner_index + 1, player_index + 1, player);
-                                
-                                transfer_from_escrow(
-                                    &ctx.accounts.escrow_pda.to_account_info(),
-                                    &account.to_account_info(),
-                                    amount_to_transfer as u64,
-                                    tournament_key,
-                                    escrow_bump,
-                                    &ctx.accounts.system_program.to_account_info(),
-                                )?;
-                                
-                                player_account_found = true;
-                                break;
-                            }
-                        }
-                        
-                        require!(player_account_found, ErrorCode::MissingWinnerAccount);
-                        
-                        remaining_amount -= amount_to_transfer;
-                        total_distributed += amount_to_transfer;
-                    }
-                    
-                    // Move position counter forward by consumed positions
-                    position_counter += *positions_consumed as usize;
-                }
-            }
-        }
+        let old_treasury = config.treasury;
+        config.treasury = new_treasury;
         
-        require!(
-            total_distributed <= tournament_pool,
-            ErrorCode::CalculationOverflow
-        );
+        msg!("Treasury updated from {} to {}", old_treasury, new_treasury);
         
-        tournament_state.phase = TournamentPhase::Finalized;
+        emit!(TreasuryUpdated {
+            old_treasury,
+            new_treasury,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
         
-        msg!("Tournament finalized, prizes distributed");
+        Ok(())
+    }
+
+    pub fn propose_admin(ctx: Context<UpdateConfig>, new_admin: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
         
-        let all_winners: Vec<Pubkey> = winners.iter().flat_map(|w| match w {
-            Winner::Individual(p) => vec![*p],
-            Winner::Group(players, _) => players.clone(),
-        }).collect();
+        config.pending_admin = Some(new_admin);
         
-        emit!(TournamentFinalized {
-            tournament: tournament_state.key(),
-            winners: all_winners,
-            total_prize_pool: total_distributed,
+        msg!("Admin transfer proposed from {} to {}", config.admin, new_admin);
+        
+        emit!(AdminTransferProposed {
+            current_admin: config.admin,
+            pending_admin: new_admin,
             timestamp: Clock::get()?.unix_timestamp,
         });
         
         Ok(())
     }
 
-    pub fn distribute_match_rewards<'a, 'b, 'c, 'info>(
-        ctx: Context<'a, 'b, 'c, 'info, DistributeMatchRewards<'info>>,
-        match_id_hash: u32,
-        winners: Vec<Winner>,
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        
+        let previous_admin = config.admin;
+        config.admin = ctx.accounts.new_admin.key();
+        config.pending_admin = None;
+        
+        msg!("Admin transferred from {} to {}", previous_admin, config.admin);
+        
+        emit!(AdminTransferAccepted {
+            previous_admin,
+            new_admin: config.admin,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn initialize_registry(ctx: Context<InitializeRegistry>) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+        
+        registry.operator = ctx.accounts.operator.key();
+        registry.next_tournament_id = 0;
+        registry.bump = ctx.bumps.registry;
+        
+        msg!("Tournament registry initialized for operator {}", registry.operator);
+        
+        emit!(RegistryInitialized {
+            registry: registry.key(),
+            operator: registry.operator,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize_tournament(
+        ctx: Context<InitializeTournament>,
+        buy_in_amount: u64,
+        max_players: u32,
+        match_size: u8,
+        tournament_prize_percentage: u16,
+        match_prize_percentage: u16,
+        operator_fee_percentage: u16,
+        min_players: u32,
+        registration_closes_at: i64,
+        unregister_penalty_percentage: u16,
+        max_play_duration: i64,
+        sweep_policy: SweepPolicy,
+        sweep_grace_period: i64,
+        tie_remainder_policy: TieRemainderPolicy,
     ) -> Result<()> {
+        let config = &ctx.accounts.config;
         let tournament_state = &mut ctx.accounts.tournament_state;
         
+        require!(buy_in_amount > 0, ErrorCode::InvalidBuyInAmount);
+        require!(max_players >= 2, ErrorCode::InvalidMaxPlayers);
+        require!(match_size >= 2 && match_size as u32 <= max_players, ErrorCode::InvalidMatchSize);
+        require!(min_players >= 1 && min_players <= max_players, ErrorCode::InvalidMinPlayers);
+        require!(unregister_penalty_percentage <= 10000, ErrorCode::InvalidUnregisterPenalty);
+        require!(max_play_duration >= 0, ErrorCode::InvalidPlayDuration);
+        require!(sweep_grace_period > 0, ErrorCode::InvalidSweepGracePeriod);
         require!(
-            tournament_state.phase == TournamentPhase::Finalized,
-            ErrorCode::TournamentNotFinalized
+            registration_closes_at > Clock::get()?.unix_timestamp,
+            ErrorCode::InvalidRegistrationDeadline
         );
-        
         require!(
-            !tournament_state.paid_match_ids.contains(&match_id_hash),
-            ErrorCode::MatchAlreadyPaid
+            tournament_prize_percentage > 0 && tournament_prize_percentage <= 10000,
+            ErrorCode::InvalidTournamentPrizePercentage
         );
-        
+        require!(match_prize_percentage <= 10000, ErrorCode::InvalidMatchPrizePercentage);
         require!(
-            !winners.is_empty(),
-            ErrorCode::InvalidWinnerCount
+            operator_fee_percentage > 0 && operator_fee_percentage <= 10000,
+            ErrorCode::InvalidOperatorFeePercentage
         );
         
-        for winner in winners.iter() {
-            match winner {
-                Winner::Individual(player) => {
-                    require!(
-                        player != &Pubkey::default(),
-                        ErrorCode::InvalidWinner
-                    );
-                    require!(is_participant(tournament_state, player), ErrorCode::WinnerNotParticipant);
-                },
-                Winner::Group(players, _) => {
-                    for player in players.iter() {
-                        require!(
-                            player != &Pubkey::default(),
-                            ErrorCode::InvalidWinner
-                        );
-                        require!(is_participant(tournament_state, player), ErrorCode::WinnerNotParticipant);
-                    }
-                }
-            }
-        }
-        
-        let total_buy_ins = calculate_total_buy_ins(tournament_state.current_players, tournament_state.buy_in_amount)?;
-        let total_match_pool = calculate_percentage_amount(total_buy_ins, tournament_state.match_prize_percentage)?;
-        
-        let num_matches = (tournament_state.current_players as u128 + tournament_state.match_size as u128 - 1) / tournament_state.match_size as u128;
-        
         require!(
-            num_matches > 0,
-            ErrorCode::InvalidMatchCount
+            tournament_prize_percentage as u32 + match_prize_percentage as u32 + operator_fee_percentage as u32 == 10000,
+            ErrorCode::InvalidPercentages
         );
         
-        let match_pool = total_match_pool / num_matches;
-        
         require!(
-            match_pool > 0,
-            ErrorCode::NoMatchRewards
+            tournament_prize_percentage >= config.min_tournament_prize_percentage,
+            ErrorCode::TournamentPrizeTooLow
         );
         
         require!(
-            match_pool * num_matches <= total_match_pool,
-            ErrorCode::CalculationOverflow
+            operator_fee_percentage <= config.max_operator_fee_percentage,
+            ErrorCode::OperatorFeeTooHigh
         );
         
-        let escrow_bump = tournament_state.escrow_bump;
-        let tournament_key = tournament_state.key();
-        
-        let mut position_counter = 0;
-        let mut total_distributed = 0u128;
-        
-        for (winner_index, winner) in winners.iter().enumerate() {
-            match winner {
-                Winner::Individual(player) => {
-                    if position_counter < tournament_state.match_payout_percentages.len() {
-                        let amount = calculate_percentage_amount(match_pool, tournament_state.match_payout_percentages[position_counter])?;
-                        
-                        require!(
-                            amount > 0,
-                            ErrorCode::NoMatchRewards
-                        );
-                        
-                        let mut winner_account_found = false;
-                        for account in ctx.remaining_accounts.iter() {
-                            if account.key() == *player && !winner_account_found {
-                                msg!("Transferring {} lamports to match winner #{} ({})", 
-                                     amount, position_counter + 1, player);
-                                
-                                transfer_from_escrow(
-                                    &ctx.accounts.escrow_pda.to_account_info(),
-                                    &account.to_account_info(),
-                                    amount as u64,
-                                    tournament_key,
-                                    escrow_bump,
-                                    &ctx.accounts.system_program.to_account_info(),
-                                )?;
-                                
-                                winner_account_found = true;
-                                break;
-                            }
-                        }
-                        
-                        require!(winner_account_found, ErrorCode::MissingWinnerAccount);
-                        total_distributed += amount;
-                    }
-                    position_counter += 1;
-                },
-                Winner::Group(players, positions_consumed) => {
-                    require!(
-                        !players.is_empty(),
-                        ErrorCode::InvalidWinnerCount
-                    );
-                    
-                    require!(
-                        *positions_consumed > 0,
-                        ErrorCode::InvalidWinnerCount
-                    );
-                    
-                    let mut combined_pool_percentage = 0u32;
-                    for i in position_counter..(position_counter + *positions_consumed as usize) {
-                        if i < tournament_state.match_payout_percentages.len() {
-                            combined_pool_percentage += tournament_state.match_payout_percentages[i] as u32;
-                        }
-                    }
-                    
-                    let combined_pool_amount = calculate_percentage_amount(match_pool, combined_pool_percentage as u16)?;
-                    
-                    let payout_per_player = combined_pool_amount / players.len() as u128;
-                    
-                    require!(
-                        payout_per_player * players.len() as u128 <= combined_pool_amount,
-                        ErrorCode::CalculationOverflow
+        tournament_state.buy_in_amount = buy_in_amount;
+        tournament_state.max_players = max_players;
+        tournament_state.min_players = min_players;
+        tournament_state.current_players = 0;
+        tournament_state.registration_closes_at = registration_closes_at;
+        tournament_state.expired = false;
+        tournament_state.unregister_penalty_percentage = unregister_penalty_percentage;
+        tournament_state.forfeited_amount = 0;
+        tournament_state.max_play_duration = max_play_duration;
+        tournament_state.started_at = 0;
+        tournament_state.match_rewards_recorded = 0;
+        tournament_state.refund_per_player = 0;
+        tournament_state.prizes_recorded = 0;
+        tournament_state.refunded_players = 0;
+        tournament_state.tournament_paid = 0;
+        tournament_state.match_paid = 0;
+        tournament_state.operator_paid = 0;
+        tournament_state.refunds_paid = 0;
+        tournament_state.unregister_refunds_paid = 0;
+        tournament_state.sweep_policy = sweep_policy;
+        tournament_state.sweep_grace_period = sweep_grace_period;
+        tournament_state.tie_remainder_policy = tie_remainder_policy;
+        tournament_state.finalized_at = 0;
+        tournament_state.remainder_swept = 0;
+        tournament_state.remainder_recorded = 0;
+        tournament_state.escrow_rent_reserve = 0;
+        tournament_state.escrow_bump = ctx.bumps.escrow_pda;
+        tournament_state.match_size = match_size;
+        tournament_state.phase = TournamentPhase::Registration;
+        tournament_state.tournament_prize_percentage = tournament_prize_percentage;
+        tournament_state.match_prize_percentage = match_prize_percentage;
+        tournament_state.operator_fee_percentage = operator_fee_percentage;
+        tournament_state.tournament_payouts = Vec::new();
+        tournament_state.match_payout_percentages = Vec::new();
+        tournament_state.payout_precision = PayoutPrecision::BasisPoints;
+        tournament_state.format = TournamentFormat::Standard;
+        tournament_state.current_round = 0;
+        tournament_state.pending_results = 0;
+        tournament_state.seating_commitment = None;
+        tournament_state.seating_commit_slot = 0;
+        tournament_state.lapsed_seating_commitments = 0;
+        tournament_state.seating_seed = None;
+        tournament_state.seated = false;
+        tournament_state.matches_created = 0;
+        tournament_state.operator_fee_withdrawn = false;
+
+        tournament_state.authority = ctx.accounts.payer.key();
+        tournament_state.operator = ctx.accounts.payer.key();
+        tournament_state.pending_authority = None;
+        tournament_state.merkle_root = None;
+        tournament_state.merkle_total = 0;
+        tournament_state.merkle_claimed_total = 0;
+        tournament_state.bump = ctx.bumps.tournament_state;
+
+        let registry = &mut ctx.accounts.registry;
+        tournament_state.tournament_id = registry.next_tournament_id;
+        registry.next_tournament_id = registry
+            .next_tournament_id
+            .checked_add(1)
+            .ok_or(ErrorCode::CalculationOverflow)?;
+
+        match &ctx.accounts.mint {
+            Some(mint) => {
+                require!(
+                    ctx.accounts.escrow_token_account.is_some() && ctx.accounts.token_program.is_some(),
+                    ErrorCode::MissingTokenAccounts
+                );
+
+                tournament_state.mint = Some(mint.key());
+                tournament_state.escrow_token_bump = ctx.bumps.escrow_token_account.unwrap_or_default();
+                tournament_state.net_buy_in_amount = net_amount_after_transfer_fee(&mint.to_account_info(), buy_in_amount)?;
+
+                require!(tournament_state.net_buy_in_amount > 0, ErrorCode::InvalidBuyInAmount);
+            },
+            None => {
+                tournament_state.mint = None;
+                tournament_state.escrow_token_bump = 0;
+                tournament_state.net_buy_in_amount = buy_in_amount;
+                
+                // Seed the native escrow with its rent-exempt minimum so payouts can never
+                // leave it holding an unpayable sliver of lamports
+                let escrow_rent_reserve = Rent::get()?
+                    .minimum_balance(0)
+                    .saturating_sub(ctx.accounts.escrow_pda.lamports());
+                
+                if escrow_rent_reserve > 0 {
+                    let cpi_context = CpiContext::new(
+                        ctx.accounts.system_program.to_account_info(),
+                        anchor_lang::system_program::Transfer {
+                            from: ctx.accounts.payer.to_account_info(),
+                            to: ctx.accounts.escrow_pda.to_account_info(),
+                        },
                     );
                     
-                    let mut remaining_amount = combined_pool_amount;
-                    for (player_index, player) in players.iter().enumerate() {
-                        let amount_to_transfer = if player_index == players.len() - 1 {
-                            remaining_amount
-                        } else {
-                            payout_per_player
-                        };
-                        
-                        require!(
-                            amount_to_transfer > 0,
-                            ErrorCode::NoMatchRewards
-                        );
-                        
-                        let mut player_account_found = false;
-                        for account in ctx.remaining_accounts.iter() {
-                            if account.key() == *player && !player_account_found {
-                                msg!("Transferring {} lamports to match tied winner group {} player {} ({})", 
-                                     amount_to_transfer, winner_index + 1, player_index + 1, player);
-                                
-                                transfer_from_escrow(
-                                    &ctx.accounts.escrow_pda.to_account_info(),
-                                    &account.to_account_info(),
-                                    amount_to_transfer as u64,
-                                    tournament_key,
-                                    escrow_bump,
-                                    &ctx.accounts.system_program.to_account_info(),
-                                )?;
-                                
-                                player_account_found = true;
-                                break;
-                            }
-                        }
-                        
-                        require!(player_account_found, ErrorCode::MissingWinnerAccount);
-                        
-                        remaining_amount -= amount_to_transfer;
-                        total_distributed += amount_to_transfer;
-                    }
-                    
-                    position_counter += *positions_consumed as usize;
+                    anchor_lang::system_program::transfer(cpi_context, escrow_rent_reserve)?;
                 }
+                
+                tournament_state.escrow_rent_reserve = escrow_rent_reserve;
             }
         }
+    
+        msg!("Tournament initialized with buy-in: {}, max players: {}, match size: {}", 
+             buy_in_amount, max_players, match_size);
+        msg!("Prize distribution: Tournament {}%, Match {}%, Operator {}%",
+             tournament_prize_percentage / 100, match_prize_percentage / 100, operator_fee_percentage / 100);
+        msg!("Escrow PDA: {}", ctx.accounts.escrow_pda.key());
+        msg!("Authority: {}", tournament_state.authority);
+        msg!("Tournament id: {}", tournament_state.tournament_id);
+        if let Some(mint) = tournament_state.mint {
+            msg!("Buy-in mint: {}, net buy-in after transfer fees: {}", mint, tournament_state.net_buy_in_amount);
+        }
         
-        require!(
-            total_distributed <= match_pool,
-            ErrorCode::CalculationOverflow
-        );
-        
-        tournament_state.paid_match_ids.push(match_id_hash);
-        
-        msg!("Match rewards distributed successfully to {} winners", winners.len());
-        
-        let all_winners: Vec<Pubkey> = winners.iter().flat_map(|w| match w {
-            Winner::Individual(p) => vec![*p],
-            Winner::Group(players, _) => players.clone(),
-        }).collect();
-        
-        emit!(MatchRewardsDistributed {
+        emit!(TournamentCreated {
             tournament: tournament_state.key(),
-            match_id: match_id_hash,
-            winners: all_winners,
-            total_match_pool: total_distributed,
+            tournament_id: tournament_state.tournament_id,
+            operator: tournament_state.operator,
+            buy_in_amount,
+            max_players,
+            min_players,
+            registration_closes_at,
+            unregister_penalty_percentage,
+            max_play_duration,
+            sweep_policy,
+            sweep_grace_period,
+            tie_remainder_policy,
+            match_size,
+            tournament_prize_percentage,
+            match_prize_percentage,
+            operator_fee_percentage,
+            authority: tournament_state.authority,
+            mint: tournament_state.mint,
             timestamp: Clock::get()?.unix_timestamp,
         });
         
         Ok(())
     }
 
-    pub fn withdraw_operator_fee(ctx: Context<WithdrawOperatorFee>) -> Result<()> {
+    pub fn buy_in(ctx: Context<BuyIn>) -> Result<()> {
         let tournament_state = &mut ctx.accounts.tournament_state;
         
         require!(
-            tournament_state.phase == TournamentPhase::Finalized,
-            ErrorCode::TournamentNotFinalized
+            tournament_state.phase == TournamentPhase::Registration, 
+            ErrorCode::InvalidPhase
         );
         
         require!(
-            !tournament_state.operator_fee_withdrawn,
-            ErrorCode::OperatorFeeAlreadyWithdrawn
+            tournament_state.current_players < tournament_state.max_players,
+            ErrorCode::TournamentFull
         );
         
-        let total_buy_ins = calculate_total_buy_ins(tournament_state.current_players, tournament_state.buy_in_amount)?;
-        let operator_fee = calculate_percentage_amount(total_buy_ins, tournament_state.operator_fee_percentage)?;
-        
-        let escrow_bump = tournament_state.escrow_bump;
-        let tournament_key = tournament_state.key();
+        require!(
+            Clock::get()?.unix_timestamp < tournament_state.registration_closes_at,
+            ErrorCode::RegistrationClosed
+        );
         
-        transfer_from_escrow(
-            &ctx.accounts.escrow_pda.to_account_info(),
-            &ctx.accounts.fee_recipient.to_account_info(),
-            operator_fee as u64,
-            tournament_key,
-            escrow_bump,
-            &ctx.accounts.system_program.to_account_info(),
+        let escrow_token = escrow_token_accounts(
+            tournament_state,
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
         )?;
+
+        if let Some(escrow_token) = escrow_token {
+            let player_token_account = payout_destination(
+                tournament_state,
+                &ctx.accounts.player.to_account_info(),
+                &ctx.accounts.player_token_account,
+            )?;
+
+            let balance_before = escrow_token.account.amount;
+
+            let cpi_context = CpiContext::new(
+                escrow_token.token_program.to_account_info(),
+                TransferChecked {
+                    from: player_token_account,
+                    mint: escrow_token.mint.to_account_info(),
+                    to: escrow_token.account.to_account_info(),
+                    authority: ctx.accounts.player.to_account_info(),
+                },
+            );
+
+            token_interface::transfer_checked(
+                cpi_context,
+                tournament_state.buy_in_amount,
+                escrow_token.mint.decimals,
+            )?;
+
+            // Transfer-fee mints withhold part of the amount, so check what actually arrived
+            let escrow_token_account = ctx.accounts.escrow_token_account.as_mut().unwrap();
+            escrow_token_account.reload()?;
+            let received = escrow_token_account.amount.saturating_sub(balance_before);
+
+            require!(
+                received >= tournament_state.net_buy_in_amount,
+                ErrorCode::TransferFeeExceeded
+            );
+        } else {
+            let cpi_context = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.player.to_account_info(),
+                    to: ctx.accounts.escrow_pda.to_account_info(),
+                },
+            );
+
+            anchor_lang::system_program::transfer(
+                cpi_context,
+                tournament_state.buy_in_amount,
+            )?;
+        }
+
+        let player_entry = &mut ctx.accounts.player_entry;
+        player_entry.tournament = tournament_state.key();
+        player_entry.player = ctx.accounts.player.key();
+        player_entry.joined_at = Clock::get()?.unix_timestamp;
+        player_entry.refunded = false;
+        player_entry.claimed = false;
+        player_entry.prize_amount = 0;
+        player_entry.match_rewards_amount = 0;
+        player_entry.prize_claimed = 0;
+        player_entry.match_claimed = 0;
+        player_entry.points = 0;
+        player_entry.tiebreak = 0;
+        player_entry.last_paired_round = 0;
+        player_entry.bump = ctx.bumps.player_entry;
         
-        tournament_state.operator_fee_withdrawn = true;
+        tournament_state.current_players = tournament_state
+            .current_players
+            .checked_add(1)
+            .ok_or(ErrorCode::PlayerCountOverflow)?;
         
-        msg!("Operator fee of {} lamports withdrawn successfully", operator_fee);
+        msg!("Player {} bought in with {} lamports", 
+             ctx.accounts.player.key(), 
+             tournament_state.buy_in_amount);
         
-        emit!(OperatorFeeWithdrawn {
+        emit!(PlayerBoughtIn {
             tournament: tournament_state.key(),
-            recipient: ctx.accounts.fee_recipient.key(),
-            amount: operator_fee,
+            player: ctx.accounts.player.key(),
+            buy_in_amount: tournament_state.buy_in_amount,
+            current_players: tournament_state.current_players,
             timestamp: Clock::get()?.unix_timestamp,
         });
         
         Ok(())
     }
 
-    pub fn cancel_tournament(ctx: Context<CancelTournament>) -> Result<()> {
+    pub fn start_tournament<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, StartTournament<'info>>,
+        payout_percentages: Vec<u32>,
+        match_payout_percentages: Vec<u32>,
+        payout_precision: PayoutPrecision,
+        format: TournamentFormat,
+    ) -> Result<()> {
         let tournament_state = &mut ctx.accounts.tournament_state;
         
         require!(
             tournament_state.phase == TournamentPhase::Registration,
-            ErrorCode::TournamentAlreadyStarted
+            ErrorCode::InvalidPhase
         );
         
-        // Mark tournament as cancelled
-        tournament_state.phase = TournamentPhase::Cancelled;
+        require!(
+            tournament_state.current_players > 0 && tournament_state.current_players >= tournament_state.min_players,
+            ErrorCode::NotEnoughPlayers
+        );
         
-        msg!("Tournament cancelled by authority");
+        require!(
+            !payout_percentages.is_empty() && payout_percentages.len() <= 20,
+            ErrorCode::InvalidPayoutCount
+        );
         
-        emit!(TournamentCancelled {
-            tournament: tournament_state.key(),
-            timestamp: Clock::get()?.unix_timestamp,
-        });
+        require!(
+            payout_percentages.len() <= tournament_state.current_players as usize,
+            ErrorCode::TooManyPayoutPositions
+        );
         
-        Ok(())
-    }
-
-    pub fn refund_participant(ctx: Context<RefundParticipant>) -> Result<()> {
-        let tournament_state = &mut ctx.accounts.tournament_state;
+        let scale = payout_precision.scale() as u64;
         
+        let total_percentage: u64 = payout_percentages.iter().map(|&p| p as u64).sum();
         require!(
-            tournament_state.phase == TournamentPhase::Cancelled,
-            ErrorCode::TournamentNotCancelled
+            total_percentage == scale,
+            ErrorCode::InvalidPercentages
         );
         
+        // Validate individual payout percentages
+        for (_i, &percentage) in payout_percentages.iter().enumerate() {
+            require!(
+                percentage > 0,
+                ErrorCode::InvalidPayoutPercentage
+            );
+            require!(
+                percentage as u64 <= scale,
+                ErrorCode::InvalidPayoutPercentage
+            );
+        }
+        
+        // Validate match payout percentages
         require!(
-            tournament_state.participants.contains(&ctx.accounts.participant.key()),
-            ErrorCode::ParticipantNotFound
+            !match_payout_percentages.is_empty() && match_payout_percentages.len() <= 8,
+            ErrorCode::InvalidMatchPayoutCount
         );
         
+        let match_total_percentage: u64 = match_payout_percentages.iter().map(|&p| p as u64).sum();
         require!(
-            !tournament_state.refunded_participants.contains(&ctx.accounts.participant.key()),
-            ErrorCode::ParticipantAlreadyRefunded
+            match_total_percentage == scale,
+            ErrorCode::InvalidMatchPayoutPercentages
         );
         
-        let escrow_bump = tournament_state.escrow_bump;
-        let tournament_key = tournament_state.key();
-        
-        // Refund the participant
-        transfer_from_escrow(
-            &ctx.accounts.escrow_pda.to_account_info(),
+        // Validate individual match payout percentages
+        for (_i, &percentage) in match_payout_percentages.iter().enumerate() {
+            require!(
+                percentage > 0,
+                ErrorCode::InvalidMatchPayoutPercentage
+            );
+            require!(
+                percentage as u64 <= scale,
+                ErrorCode::InvalidMatchPayoutPercentage
+            );
+        }
+        
+        tournament_state.tournament_payouts = payout_percentages;
+        tournament_state.match_payout_percentages = match_payout_percentages;
+        tournament_state.payout_precision = payout_precision;
+        tournament_state.format = format;
+        
+        if format == TournamentFormat::SingleElimination {
+            // The seeds are filled in afterwards by seed_bracket_page, since a large field's
+            // entries do not fit in one transaction
+            let size = bracket_size(tournament_state.current_players)?;
+            
+            let bracket = ctx.accounts.bracket.as_mut().ok_or(ErrorCode::MissingBracket)?;
+            bracket.tournament = tournament_state.key();
+            bracket.size = size;
+            bracket.bump = ctx.bumps.bracket.unwrap_or_default();
+            bracket.seeded = 0;
+            bracket.nodes = vec![Pubkey::default(); 2 * size as usize - 1];
+            
+            msg!("Bracket of {} slots created for {} players", size, tournament_state.current_players);
+        }
+        
+        if format == TournamentFormat::Swiss {
+            // Swiss rounds are head-to-head
+            require!(tournament_state.match_size == 2, ErrorCode::InvalidMatchSize);
+            tournament_state.current_round = 1;
+            
+            let swiss_standings = ctx.accounts.swiss_standings.as_mut().ok_or(ErrorCode::MissingStandings)?;
+            swiss_standings.tournament = tournament_state.key();
+            swiss_standings.bump = ctx.bumps.swiss_standings.unwrap_or_default();
+            swiss_standings.records = Vec::new();
+        }
+        
+        tournament_state.phase = TournamentPhase::Playing;
+        tournament_state.started_at = Clock::get()?.unix_timestamp;
+        
+        msg!("Tournament started with {} players and {} payout positions", 
+             tournament_state.current_players, tournament_state.tournament_payouts.len());
+        msg!("Match payouts: {} positions with {}% total", 
+             tournament_state.match_payout_percentages.len(), match_total_percentage * 100 / scale);
+        
+        emit!(TournamentStarted {
+            tournament: tournament_state.key(),
+            current_players: tournament_state.current_players,
+            format,
+            payout_percentages: tournament_state.tournament_payouts.clone(),
+            match_payout_percentages: tournament_state.match_payout_percentages.clone(),
+            payout_precision,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn finalize_tournament<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, FinalizeTournament<'info>>,
+        winners: Vec<Winner>
+    ) -> Result<()> {
+        let winners = settlement_winners(&ctx.accounts.tournament_state, &ctx.accounts.bracket, &ctx.accounts.swiss_standings, winners)?;
+        let payouts = plan_tournament_settlement(&ctx.accounts.tournament_state, ctx.remaining_accounts, &winners)?;
+        
+        let tournament_state = &mut ctx.accounts.tournament_state;
+        let tournament_key = tournament_state.key();
+        
+        let total_distributed = record_payouts(ctx.remaining_accounts, &tournament_key, &payouts, PrizeSource::Tournament)?;
+        
+        tournament_state.prizes_recorded = total_distributed as u64;
+        tournament_state.phase = TournamentPhase::Finalized;
+        tournament_state.finalized_at = Clock::get()?.unix_timestamp;
+        
+        msg!("Tournament finalized, prizes recorded for claiming");
+        
+        let all_winners = winner_keys(&winners);
+        
+        emit!(TournamentFinalized {
+            tournament: tournament_state.key(),
+            winners: all_winners,
+            total_prize_pool: total_distributed,
+            tie_remainder_policy: tournament_state.tie_remainder_policy,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn distribute_match_rewards<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, DistributeMatchRewards<'info>>,
+        match_id: [u8; 32],
+        winners: Vec<Winner>,
+    ) -> Result<()> {
+        let payouts = plan_match_settlement(&ctx.accounts.tournament_state, &ctx.accounts.match_state, ctx.remaining_accounts, &winners)?;
+        
+        let tournament_state = &mut ctx.accounts.tournament_state;
+        let tournament_key = tournament_state.key();
+        
+        let total_distributed = record_payouts(ctx.remaining_accounts, &tournament_key, &payouts, PrizeSource::Match)?;
+        
+        let match_state = &mut ctx.accounts.match_state;
+        match_state.paid = true;
+        match_state.paid_at = Clock::get()?.unix_timestamp;
+        match_state.rewards_amount = total_distributed as u64;
+        
+        tournament_state.match_rewards_recorded = tournament_state
+            .match_rewards_recorded
+            .checked_add(total_distributed as u64)
+            .ok_or(ErrorCode::CalculationOverflow)?;
+        
+        msg!("Match rewards recorded for {} winners", winners.len());
+        
+        let all_winners = winner_keys(&winners);
+        
+        emit!(MatchRewardsDistributed {
+            tournament: tournament_state.key(),
+            match_id,
+            winners: all_winners,
+            total_match_pool: total_distributed,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn withdraw_operator_fee(ctx: Context<WithdrawOperatorFee>) -> Result<()> {
+        let tournament_state = &mut ctx.accounts.tournament_state;
+        
+        require!(
+            tournament_state.phase == TournamentPhase::Finalized,
+            ErrorCode::TournamentNotFinalized
+        );
+        
+        require!(
+            !tournament_state.operator_fee_withdrawn,
+            ErrorCode::OperatorFeeAlreadyWithdrawn
+        );
+        
+        let total_buy_ins = calculate_total_buy_ins(tournament_state.current_players, tournament_state.net_buy_in_amount)?;
+        let operator_fee = calculate_percentage_amount(total_buy_ins, BasisPoints::new(tournament_state.operator_fee_percentage)?)?;
+        
+        let escrow_bump = tournament_state.escrow_bump;
+        let tournament_key = tournament_state.key();
+        let escrow_token = escrow_token_accounts(
+            tournament_state,
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+        )?;
+        let fee_destination = payout_destination(
+            tournament_state,
+            &ctx.accounts.fee_recipient.to_account_info(),
+            &ctx.accounts.fee_recipient_token_account,
+        )?;
+        
+        transfer_from_escrow(
+            &ctx.accounts.escrow_pda.to_account_info(),
+            escrow_token.as_ref(),
+            &fee_destination,
+            operator_fee as u64,
+            tournament_key,
+            escrow_bump,
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        
+        tournament_state.operator_fee_withdrawn = true;
+        tournament_state.operator_paid = operator_fee as u64;
+        check_escrow_invariants(tournament_state)?;
+        
+        msg!("Operator fee of {} lamports withdrawn successfully", operator_fee);
+        
+        emit!(OperatorFeeWithdrawn {
+            tournament: tournament_state.key(),
+            recipient: ctx.accounts.fee_recipient.key(),
+            amount: operator_fee,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn cancel_tournament(ctx: Context<CancelTournament>) -> Result<()> {
+        let tournament_state = &mut ctx.accounts.tournament_state;
+        
+        require!(
+            tournament_state.phase == TournamentPhase::Registration,
+            ErrorCode::TournamentAlreadyStarted
+        );
+        
+        // Mark tournament as cancelled
+        tournament_state.phase = TournamentPhase::Cancelled;
+        tournament_state.refund_per_player = calculate_cancellation_refund(tournament_state)?;
+        
+        msg!("Tournament cancelled by authority");
+        
+        emit!(TournamentCancelled {
+            tournament: tournament_state.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn refund_participant(ctx: Context<RefundParticipant>) -> Result<()> {
+        let tournament_state = &mut ctx.accounts.tournament_state;
+        
+        require!(
+            tournament_state.phase == TournamentPhase::Cancelled,
+            ErrorCode::TournamentNotCancelled
+        );
+        
+        let escrow_bump = tournament_state.escrow_bump;
+        let tournament_key = tournament_state.key();
+        let escrow_token = escrow_token_accounts(
+            tournament_state,
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+        )?;
+        let refund_destination = payout_destination(
+            tournament_state,
             &ctx.accounts.participant.to_account_info(),
-            tournament_state.buy_in_amount,
+            &ctx.accounts.participant_token_account,
+        )?;
+        
+        // Refund the participant
+        transfer_from_escrow(
+            &ctx.accounts.escrow_pda.to_account_info(),
+            escrow_token.as_ref(),
+            &refund_destination,
+            tournament_state.refund_per_player,
             tournament_key,
             escrow_bump,
             &ctx.accounts.system_program.to_account_info(),
         )?;
         
         // Mark participant as refunded
-        tournament_state.refunded_participants.push(ctx.accounts.participant.key());
+        ctx.accounts.player_entry.refunded = true;
+        tournament_state.refunded_players = tournament_state
+            .refunded_players
+            .checked_add(1)
+            .ok_or(ErrorCode::PlayerCountOverflow)?;
+        tournament_state.refunds_paid = tournament_state
+            .refunds_paid
+            .checked_add(tournament_state.refund_per_player)
+            .ok_or(ErrorCode::CalculationOverflow)?;
+        check_escrow_invariants(tournament_state)?;
         
-        msg!("Refunded {} lamports to participant {}", 
-             tournament_state.buy_in_amount, ctx.accounts.participant.key());
+        msg!("Refunded {} lamports to participant {} (called by {})", 
+             tournament_state.refund_per_player, ctx.accounts.participant.key(), ctx.accounts.caller.key());
         
         emit!(ParticipantRefunded {
             tournament: tournament_state.key(),
             participant: ctx.accounts.participant.key(),
-            amount: tournament_state.buy_in_amount,
+            amount: tournament_state.refund_per_player,
             timestamp: Clock::get()?.unix_timestamp,
         });
         
         Ok(())
     }
 
-}
+    pub fn propose_authority(ctx: Context<ProposeAuthority>, new_authority: Pubkey) -> Result<()> {
+        let tournament_state = &mut ctx.accounts.tournament_state;
+        
+        tournament_state.pending_authority = Some(new_authority);
+        
+        msg!("Authority transfer proposed from {} to {}", tournament_state.authority, new_authority);
+        
+        emit!(AuthorityTransferProposed {
+            tournament: tournament_state.key(),
+            current_authority: tournament_state.authority,
+            pending_authority: new_authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
 
-#[account]
-pub struct TournamentState {
-    pub buy_in_amount: u64,    
-    pub max_players: u8,       
-    pub current_players: u8, 
-    pub escrow_bump: u8,       
-    pub match_size: u8,     
-    pub phase: TournamentPhase, 
-    pub participants: Vec<Pubkey>, 
-    pub paid_match_ids: Vec<u32>,
-    pub tournament_prize_percentage: u16,  
-    pub match_prize_percentage: u16,    
-    pub operator_fee_percentage: u16,  
-    pub tournament_payouts: Vec<u16>, 
-    pub match_payout_percentages: Vec<u16>,
-    pub operator_fee_withdrawn: bool,  
-    pub authority: Pubkey,    
-    pub refunded_participants: Vec<Pubkey>,
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        let tournament_state = &mut ctx.accounts.tournament_state;
+        
+        let previous_authority = tournament_state.authority;
+        tournament_state.authority = ctx.accounts.new_authority.key();
+        tournament_state.pending_authority = None;
+        
+        msg!("Authority transferred from {} to {}", previous_authority, tournament_state.authority);
+        
+        emit!(AuthorityTransferAccepted {
+            tournament: tournament_state.key(),
+            previous_authority,
+            new_authority: tournament_state.authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn claim_prize(ctx: Context<ClaimPrize>) -> Result<()> {
+        let tournament_state = &mut ctx.accounts.tournament_state;
+        
+        let (prize_due, match_due) = ctx.accounts.player_entry.settle_claim()?;
+        let amount = prize_due
+            .checked_add(match_due)
+            .ok_or(ErrorCode::CalculationOverflow)?;
+        
+        require!(amount > 0, ErrorCode::NothingToClaim);
+        
+        let escrow_bump = tournament_state.escrow_bump;
+        let tournament_key = tournament_state.key();
+        let escrow_token = escrow_token_accounts(
+            tournament_state,
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+        )?;
+        let prize_destination = payout_destination(
+            tournament_state,
+            &ctx.accounts.player.to_account_info(),
+            &ctx.accounts.player_token_account,
+        )?;
+        
+        transfer_from_escrow(
+            &ctx.accounts.escrow_pda.to_account_info(),
+            escrow_token.as_ref(),
+            &prize_destination,
+            amount,
+            tournament_key,
+            escrow_bump,
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        
+        tournament_state.tournament_paid = tournament_state
+            .tournament_paid
+            .checked_add(prize_due)
+            .ok_or(ErrorCode::CalculationOverflow)?;
+        tournament_state.match_paid = tournament_state
+            .match_paid
+            .checked_add(match_due)
+            .ok_or(ErrorCode::CalculationOverflow)?;
+        check_escrow_invariants(tournament_state)?;
+        
+        let player_entry = &ctx.accounts.player_entry;
+        
+        msg!("Claimed {} lamports for player {}", amount, player_entry.player);
+        
+        emit!(PrizeClaimed {
+            tournament: tournament_key,
+            player: player_entry.player,
+            claimer: ctx.accounts.claimer.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn finalize_tournament_with_merkle_root(
+        ctx: Context<FinalizeTournamentWithMerkleRoot>,
+        merkle_root: [u8; 32],
+        total_amount: u64,
+        leaf_count: u32,
+    ) -> Result<()> {
+        let tournament_state = &mut ctx.accounts.tournament_state;
+        
+        require!(
+            tournament_state.phase == TournamentPhase::Playing,
+            ErrorCode::InvalidPhase
+        );
+        
+        // Bracket and Swiss tournaments derive their own standings, so an operator-built root would bypass them
+        require!(
+            tournament_state.format == TournamentFormat::Standard,
+            ErrorCode::WinnersFromStandings
+        );
+        
+        require!(
+            leaf_count > 0,
+            ErrorCode::InvalidWinnerCount
+        );
+        
+        let tournament_pool = calculate_tournament_pool(tournament_state)?;
+        
+        require!(
+            total_amount as u128 <= tournament_pool,
+            ErrorCode::MerkleTotalExceedsPool
+        );
+        
+        let claim_bitmap = &mut ctx.accounts.claim_bitmap;
+        claim_bitmap.tournament = tournament_state.key();
+        claim_bitmap.leaf_count = leaf_count;
+        claim_bitmap.bump = ctx.bumps.claim_bitmap;
+        claim_bitmap.claimed = vec![0u8; ClaimBitmap::bitmap_len(leaf_count)];
+        
+        tournament_state.merkle_root = Some(merkle_root);
+        tournament_state.merkle_total = total_amount;
+        tournament_state.merkle_claimed_total = 0;
+        tournament_state.phase = TournamentPhase::Finalized;
+        tournament_state.finalized_at = Clock::get()?.unix_timestamp;
+        
+        msg!("Tournament finalized with merkle root over {} leaves totalling {} lamports", 
+             leaf_count, total_amount);
+        
+        emit!(TournamentFinalizedWithMerkleRoot {
+            tournament: tournament_state.key(),
+            merkle_root,
+            total_amount,
+            leaf_count,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn claim_with_proof(
+        ctx: Context<ClaimWithProof>,
+        index: u32,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let tournament_state = &mut ctx.accounts.tournament_state;
+        let claim_bitmap = &mut ctx.accounts.claim_bitmap;
+        
+        let merkle_root = tournament_state.merkle_root.ok_or(ErrorCode::MerkleRootNotSet)?;
+        
+        require!(
+            index < claim_bitmap.leaf_count,
+            ErrorCode::InvalidMerkleProof
+        );
+        
+        let leaf = merkle_leaf(index, &ctx.accounts.player.key(), amount);
+        require!(
+            verify_merkle_proof(&proof, &merkle_root, leaf),
+            ErrorCode::InvalidMerkleProof
+        );
+        
+        let byte_index = (index / 8) as usize;
+        let bit_mask = 1u8 << (index % 8);
+        require!(
+            claim_bitmap.claimed[byte_index] & bit_mask == 0,
+            ErrorCode::AlreadyClaimed
+        );
+        
+        let merkle_claimed_total = tournament_state
+            .merkle_claimed_total
+            .checked_add(amount)
+            .ok_or(ErrorCode::CalculationOverflow)?;
+        require!(
+            merkle_claimed_total <= tournament_state.merkle_total,
+            ErrorCode::MerkleTotalExceedsPool
+        );
+        
+        let escrow_bump = tournament_state.escrow_bump;
+        let tournament_key = tournament_state.key();
+        let escrow_token = escrow_token_accounts(
+            tournament_state,
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+        )?;
+        let prize_destination = payout_destination(
+            tournament_state,
+            &ctx.accounts.player.to_account_info(),
+            &ctx.accounts.player_token_account,
+        )?;
+        
+        transfer_from_escrow(
+            &ctx.accounts.escrow_pda.to_account_info(),
+            escrow_token.as_ref(),
+            &prize_destination,
+            amount,
+            tournament_key,
+            escrow_bump,
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        
+        claim_bitmap.claimed[byte_index] |= bit_mask;
+        tournament_state.merkle_claimed_total = merkle_claimed_total;
+        tournament_state.tournament_paid = tournament_state
+            .tournament_paid
+            .checked_add(amount)
+            .ok_or(ErrorCode::CalculationOverflow)?;
+        check_escrow_invariants(tournament_state)?;
+        
+        let player_entry = &mut ctx.accounts.player_entry;
+        player_entry.prize_amount = player_entry.prize_amount.checked_add(amount).ok_or(ErrorCode::CalculationOverflow)?;
+        player_entry.prize_claimed = player_entry.prize_claimed.checked_add(amount).ok_or(ErrorCode::CalculationOverflow)?;
+        player_entry.claimed = true;
+        
+        msg!("Claimed {} lamports for merkle leaf {} ({})", amount, index, player_entry.player);
+        
+        emit!(PrizeClaimed {
+            tournament: tournament_key,
+            player: player_entry.player,
+            claimer: ctx.accounts.claimer.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn expire_tournament(ctx: Context<ExpireTournament>) -> Result<()> {
+        let tournament_state = &mut ctx.accounts.tournament_state;
+        let now = Clock::get()?.unix_timestamp;
+        
+        require!(
+            tournament_state.phase == TournamentPhase::Registration,
+            ErrorCode::InvalidPhase
+        );
+        
+        require!(
+            now >= tournament_state.registration_closes_at,
+            ErrorCode::RegistrationStillOpen
+        );
+        
+        require!(
+            tournament_state.current_players < tournament_state.min_players,
+            ErrorCode::MinPlayersReached
+        );
+        
+        tournament_state.phase = TournamentPhase::Cancelled;
+        tournament_state.refund_per_player = calculate_cancellation_refund(tournament_state)?;
+        tournament_state.expired = true;
+        
+        msg!("Tournament expired with {} of {} required players", 
+             tournament_state.current_players, tournament_state.min_players);
+        
+        emit!(TournamentExpired {
+            tournament: tournament_state.key(),
+            current_players: tournament_state.current_players,
+            min_players: tournament_state.min_players,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    pub fn unregister(ctx: Context<Unregister>) -> Result<()> {
+        let tournament_state = &mut ctx.accounts.tournament_state;
+        
+        require!(
+            tournament_state.phase == TournamentPhase::Registration,
+            ErrorCode::InvalidPhase
+        );
+        
+        let penalty_amount = calculate_percentage_amount(
+            tournament_state.net_buy_in_amount as u128,
+            BasisPoints::new(tournament_state.unregister_penalty_percentage)?,
+        )? as u64;
+        let refund_amount = tournament_state
+            .net_buy_in_amount
+            .checked_sub(penalty_amount)
+            .ok_or(ErrorCode::CalculationOverflow)?;
+        
+        let escrow_bump = tournament_state.escrow_bump;
+        let tournament_key = tournament_state.key();
+        let escrow_token = escrow_token_accounts(
+            tournament_state,
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+        )?;
+        let refund_destination = payout_destination(
+            tournament_state,
+            &ctx.accounts.player.to_account_info(),
+            &ctx.accounts.player_token_account,
+        )?;
+        
+        if refund_amount > 0 {
+            transfer_from_escrow(
+                &ctx.accounts.escrow_pda.to_account_info(),
+                escrow_token.as_ref(),
+                &refund_destination,
+                refund_amount,
+                tournament_key,
+                escrow_bump,
+                &ctx.accounts.system_program.to_account_info(),
+            )?;
+        }
+        
+        // Penalty stays in escrow and is added to the tournament prize pool
+        tournament_state.forfeited_amount = tournament_state
+            .forfeited_amount
+            .checked_add(penalty_amount)
+            .ok_or(ErrorCode::CalculationOverflow)?;
+        tournament_state.current_players = tournament_state
+            .current_players
+            .checked_sub(1)
+            .ok_or(ErrorCode::PlayerCountOverflow)?;
+        tournament_state.refunds_paid = tournament_state
+            .refunds_paid
+            .checked_add(refund_amount)
+            .ok_or(ErrorCode::CalculationOverflow)?;
+        tournament_state.unregister_refunds_paid = tournament_state
+            .unregister_refunds_paid
+            .checked_add(refund_amount)
+            .ok_or(ErrorCode::CalculationOverflow)?;
+        check_escrow_invariants(tournament_state)?;
+        
+        msg!("Player {} unregistered, refunded {} lamports with {} lamports penalty", 
+             ctx.accounts.player.key(), refund_amount, penalty_amount);
+        
+        emit!(PlayerUnregistered {
+            tournament: tournament_key,
+            player: ctx.accounts.player.key(),
+            refund_amount,
+            penalty_amount,
+            current_players: tournament_state.current_players,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn emergency_cancel(ctx: Context<EmergencyCancel>, reason_code: u8) -> Result<()> {
+        let tournament_state = &mut ctx.accounts.tournament_state;
+        let now = Clock::get()?.unix_timestamp;
+        
+        require!(
+            tournament_state.phase == TournamentPhase::Playing,
+            ErrorCode::InvalidPhase
+        );
+        
+        // Anyone may cancel once the configured play duration has elapsed
+        let timed_out = tournament_state.max_play_duration > 0
+            && now >= tournament_state.started_at.saturating_add(tournament_state.max_play_duration);
+        
+        require!(
+            timed_out || ctx.accounts.caller.key() == tournament_state.authority,
+            ErrorCode::UnauthorizedAuthority
+        );
+        
+        tournament_state.refund_per_player = calculate_cancellation_refund(tournament_state)?;
+        tournament_state.phase = TournamentPhase::Cancelled;
+        
+        msg!("Tournament emergency cancelled with reason {}, refunding {} lamports per player", 
+             reason_code, tournament_state.refund_per_player);
+        
+        emit!(TournamentEmergencyCancelled {
+            tournament: tournament_state.key(),
+            cancelled_by: ctx.accounts.caller.key(),
+            reason_code,
+            timed_out,
+            refund_per_player: tournament_state.refund_per_player,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    pub fn close_tournament(ctx: Context<CloseTournament>) -> Result<()> {
+        let tournament_state = &ctx.accounts.tournament_state;
+        
+        match tournament_state.phase {
+            TournamentPhase::Finalized => {
+                require!(
+                    tournament_state.operator_fee_withdrawn,
+                    ErrorCode::OperatorFeeNotWithdrawn
+                );
+                
+                // Late match rewards can still be paid until the grace period is over
+                let sweep_opens_at = tournament_state
+                    .finalized_at
+                    .checked_add(tournament_state.sweep_grace_period)
+                    .ok_or(ErrorCode::CalculationOverflow)?;
+                require!(
+                    Clock::get()?.unix_timestamp >= sweep_opens_at,
+                    ErrorCode::SweepGracePeriodActive
+                );
+            },
+            TournamentPhase::Cancelled => {
+                require!(
+                    tournament_state.refunded_players == tournament_state.current_players,
+                    ErrorCode::RefundsOutstanding
+                );
+            },
+            _ => return err!(ErrorCode::InvalidPhase),
+        }
+        
+        // With nothing owed, whatever the escrow still holds belongs to no bucket, so the transfers
+        // below empty it without going through check_escrow_invariants
+        require!(
+            calculate_outstanding_obligations(tournament_state)? == 0,
+            ErrorCode::PrizesOutstanding
+        );
+        
+        if tournament_state.merkle_root.is_some() {
+            require!(
+                ctx.accounts.claim_bitmap.is_some(),
+                ErrorCode::MerkleRootNotSet
+            );
+        }
+        
+        // Accounts a finished tournament is known to have are closed along with it
+        if tournament_state.phase == TournamentPhase::Finalized {
+            match tournament_state.format {
+                TournamentFormat::SingleElimination => require!(ctx.accounts.bracket.is_some(), ErrorCode::MissingBracket),
+                TournamentFormat::Swiss => require!(ctx.accounts.swiss_standings.is_some(), ErrorCode::MissingStandings),
+                TournamentFormat::Standard => {},
+            }
+        }
+        if tournament_state.seating_commitment.is_some() {
+            require!(ctx.accounts.seating.is_some(), ErrorCode::MissingSeating);
+        }
+        
+        let escrow_bump = tournament_state.escrow_bump;
+        let tournament_key = tournament_state.key();
+        let escrow_pda = ctx.accounts.escrow_pda.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+        let escrow_token = escrow_token_accounts(
+            tournament_state,
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+        )?;
+        let seeds = &[b"escrow", tournament_key.as_ref(), &[escrow_bump]];
+        let signer = &[&seeds[..]];
+        
+        // Unless the policy already names the treasury, the remainder has to leave through sweep_remainder
+        if tournament_state.phase == TournamentPhase::Finalized && tournament_state.sweep_policy != SweepPolicy::Treasury {
+            let escrow_balance = match escrow_token.as_ref() {
+                Some(escrow_token) => escrow_token.account.amount,
+                None => escrow_pda.lamports().saturating_sub(tournament_state.escrow_rent_reserve),
+            };
+            require!(escrow_balance == 0, ErrorCode::RemainderNotSwept);
+        }
+        
+        let mut swept_amount = 0u64;
+        
+        if let Some(escrow_token) = escrow_token.as_ref() {
+            swept_amount = escrow_token.account.amount;
+            
+            if swept_amount > 0 {
+                let treasury_destination = payout_destination(
+                    tournament_state,
+                    &ctx.accounts.treasury.to_account_info(),
+                    &ctx.accounts.treasury_token_account,
+                )?;
+                
+                transfer_from_escrow(
+                    &escrow_pda,
+                    Some(escrow_token),
+                    &treasury_destination,
+                    swept_amount,
+                    tournament_key,
+                    escrow_bump,
+                    &system_program,
+                )?;
+            }
+            
+            // Withheld Token-2022 fees block closing the account, so move them to the mint first
+            if has_transfer_fee(&escrow_token.mint.to_account_info())? {
+                harvest_withheld_tokens_to_mint(
+                    CpiContext::new(
+                        escrow_token.token_program.to_account_info(),
+                        HarvestWithheldTokensToMint {
+                            token_program_id: escrow_token.token_program.to_account_info(),
+                            mint: escrow_token.mint.to_account_info(),
+                        },
+                    ),
+                    vec![escrow_token.account.to_account_info()],
+                )?;
+            }
+            
+            token_interface::close_account(CpiContext::new_with_signer(
+                escrow_token.token_program.to_account_info(),
+                CloseAccount {
+                    account: escrow_token.account.to_account_info(),
+                    destination: ctx.accounts.authority.to_account_info(),
+                    authority: escrow_pda.clone(),
+                },
+                signer,
+            ))?;
+        }
+        
+        // Native balance above the rent reserve (the dust of a SOL tournament) goes to the treasury
+        let escrow_dust = escrow_pda.lamports().saturating_sub(tournament_state.escrow_rent_reserve);
+        if escrow_dust > 0 {
+            transfer_from_escrow(
+                &escrow_pda,
+                None,
+                &ctx.accounts.treasury.to_account_info(),
+                escrow_dust,
+                tournament_key,
+                escrow_bump,
+                &system_program,
+            )?;
+            
+            if tournament_state.mint.is_none() {
+                swept_amount = escrow_dust;
+            }
+        }
+        
+        // The rent reserve goes back with the state rent to the current authority, who takes over the
+        // creator's deposits along with the tournament
+        let escrow_rent_reserve = escrow_pda.lamports();
+        if escrow_rent_reserve > 0 {
+            transfer_from_escrow(
+                &escrow_pda,
+                None,
+                &ctx.accounts.authority.to_account_info(),
+                escrow_rent_reserve,
+                tournament_key,
+                escrow_bump,
+                &system_program,
+            )?;
+        }
+        
+        msg!("Tournament {} closed, swept {} to treasury {}", 
+             tournament_key, swept_amount, ctx.accounts.treasury.key());
+        
+        emit!(TournamentClosed {
+            tournament: tournament_key,
+            closed_by: ctx.accounts.authority.key(),
+            rent_recipient: ctx.accounts.authority.key(),
+            treasury: ctx.accounts.treasury.key(),
+            swept_amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn sweep_remainder<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, SweepRemainder<'info>>) -> Result<()> {
+        let tournament_state = &mut ctx.accounts.tournament_state;
+        
+        require!(
+            tournament_state.phase == TournamentPhase::Finalized,
+            ErrorCode::TournamentNotFinalized
+        );
+        
+        let sweep_opens_at = tournament_state
+            .finalized_at
+            .checked_add(tournament_state.sweep_grace_period)
+            .ok_or(ErrorCode::CalculationOverflow)?;
+        require!(
+            Clock::get()?.unix_timestamp >= sweep_opens_at,
+            ErrorCode::SweepGracePeriodActive
+        );
+        
+        // No recipient means the remainder is recorded for the winners; merkle settlements have
+        // no prizes on-chain to weigh it by, so their remainder goes to the treasury
+        let expected_recipient = match (tournament_state.sweep_policy, tournament_state.merkle_root) {
+            (SweepPolicy::Winners, None) => None,
+            (SweepPolicy::Operator, _) => Some(tournament_state.authority),
+            _ => Some(ctx.accounts.config.treasury),
+        };
+        
+        let escrow_bump = tournament_state.escrow_bump;
+        let tournament_key = tournament_state.key();
+        let escrow_token = escrow_token_accounts(
+            tournament_state,
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+        )?;
+        let escrow_balance = match escrow_token.as_ref() {
+            Some(escrow_token) => escrow_token.account.amount,
+            None => ctx.accounts.escrow_pda.lamports().saturating_sub(tournament_state.escrow_rent_reserve),
+        };
+        
+        // Only the balance above everything still owed is swept, so no bucket can be overdrawn here
+        // and the sweep is tracked in remainder_swept or remainder_recorded rather than checked
+        // against a bucket
+        let outstanding = calculate_outstanding_obligations(tournament_state)?;
+        let remainder = (escrow_balance as u128).saturating_sub(outstanding) as u64;
+        
+        require!(remainder > 0, ErrorCode::NothingToSweep);
+        
+        match expected_recipient {
+            None => {
+                // Winner entries are passed in remaining_accounts, ordered by player
+                let prizes_total = tournament_state
+                    .prizes_recorded
+                    .checked_add(tournament_state.remainder_recorded)
+                    .ok_or(ErrorCode::CalculationOverflow)?;
+                record_remainder_shares(ctx.remaining_accounts, &tournament_key, prizes_total, remainder)?;
+                
+                tournament_state.remainder_recorded = tournament_state
+                    .remainder_recorded
+                    .checked_add(remainder)
+                    .ok_or(ErrorCode::CalculationOverflow)?;
+                
+                msg!("Recorded {} lamports of escrow remainder for the winners (called by {})", 
+                     remainder, ctx.accounts.caller.key());
+            },
+            Some(expected_recipient) => {
+                let recipient = ctx.accounts.recipient.as_ref().ok_or(ErrorCode::InvalidSweepRecipient)?;
+                require_keys_eq!(recipient.key(), expected_recipient, ErrorCode::InvalidSweepRecipient);
+                
+                let sweep_destination = payout_destination(
+                    tournament_state,
+                    &recipient.to_account_info(),
+                    &ctx.accounts.recipient_token_account,
+                )?;
+                
+                transfer_from_escrow(
+                    &ctx.accounts.escrow_pda.to_account_info(),
+                    escrow_token.as_ref(),
+                    &sweep_destination,
+                    remainder,
+                    tournament_key,
+                    escrow_bump,
+                    &ctx.accounts.system_program.to_account_info(),
+                )?;
+                
+                tournament_state.remainder_swept = tournament_state
+                    .remainder_swept
+                    .checked_add(remainder)
+                    .ok_or(ErrorCode::CalculationOverflow)?;
+                
+                msg!("Swept {} lamports of escrow remainder to {} (called by {})", 
+                     remainder, expected_recipient, ctx.accounts.caller.key());
+            }
+        }
+        
+        emit!(RemainderSwept {
+            tournament: tournament_key,
+            policy: tournament_state.sweep_policy,
+            recipient: expected_recipient,
+            amount: remainder,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn preview_finalize<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, PreviewSettlement<'info>>,
+        winners: Vec<Winner>,
+    ) -> Result<Vec<PlannedPayout>> {
+        let winners = settlement_winners(&ctx.accounts.tournament_state, &ctx.accounts.bracket, &ctx.accounts.swiss_standings, winners)?;
+        let payouts = plan_tournament_settlement(&ctx.accounts.tournament_state, ctx.remaining_accounts, &winners)?;
+        
+        msg!("Previewed {} tournament payouts", payouts.len());
+        
+        preview_payouts(payouts)
+    }
+
+    pub fn preview_match_rewards<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, PreviewMatchRewards<'info>>,
+        _match_id: [u8; 32],
+        winners: Vec<Winner>,
+    ) -> Result<Vec<PlannedPayout>> {
+        let payouts = plan_match_settlement(&ctx.accounts.tournament_state, &ctx.accounts.match_state, ctx.remaining_accounts, &winners)?;
+        
+        msg!("Previewed {} match payouts", payouts.len());
+        
+        preview_payouts(payouts)
+    }
+
+    pub fn create_match<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, CreateMatch<'info>>,
+        match_id: [u8; 32],
+        players: Vec<Pubkey>,
+    ) -> Result<()> {
+        let tournament_state = &mut ctx.accounts.tournament_state;
+        
+        require!(
+            tournament_state.phase == TournamentPhase::Playing,
+            ErrorCode::InvalidPhase
+        );
+        
+        // A committed seating has to be used, so matches wait until every player is seated
+        require!(
+            tournament_state.seating_commitment.is_none() || tournament_state.seated,
+            ErrorCode::SeatingPending
+        );
+        
+        require!(
+            !players.is_empty() && players.len() <= tournament_state.match_size as usize,
+            ErrorCode::InvalidMatchRoster
+        );
+        
+        let tournament_key = tournament_state.key();
+        let mut seated_group = None;
+        for (index, player) in players.iter().enumerate() {
+            require!(
+                !players[..index].contains(player),
+                ErrorCode::DuplicateMatchPlayer
+            );
+            player_entry_at(ctx.remaining_accounts, index, &tournament_key, player)?;
+            
+            // Once players are seated, a match may only bring together players from one group
+            if tournament_state.seated {
+                let seating = ctx.accounts.seating.as_ref().ok_or(ErrorCode::MissingSeating)?;
+                let seat = seating
+                    .players
+                    .iter()
+                    .position(|seated| seated == player)
+                    .ok_or(ErrorCode::RosterNotSeated)?;
+                let group = seat / tournament_state.match_size as usize;
+                require!(*seated_group.get_or_insert(group) == group, ErrorCode::RosterNotSeated);
+            }
+        }
+        
+        let match_state = &mut ctx.accounts.match_state;
+        match_state.tournament = tournament_key;
+        match_state.match_id = match_id;
+        match_state.paid = false;
+        match_state.paid_at = 0;
+        match_state.rewards_amount = 0;
+        match_state.bump = ctx.bumps.match_state;
+        match_state.players = players;
+        
+        tournament_state.matches_created = tournament_state
+            .matches_created
+            .checked_add(1)
+            .ok_or(ErrorCode::CalculationOverflow)?;
+        
+        msg!("Match created with {} players", match_state.players.len());
+        
+        emit!(MatchCreated {
+            tournament: tournament_key,
+            match_id,
+            players: match_state.players.clone(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn report_match_result(ctx: Context<ReportMatchResult>, node_index: u32, winner: Pubkey) -> Result<()> {
+        let tournament_state = &ctx.accounts.tournament_state;
+        
+        require!(
+            tournament_state.phase == TournamentPhase::Playing,
+            ErrorCode::InvalidPhase
+        );
+        
+        let bracket = &mut ctx.accounts.bracket;
+        require!(
+            bracket.seeded == tournament_state.current_players,
+            ErrorCode::BracketNotSeeded
+        );
+        
+        let loser = report_result(&mut bracket.nodes, node_index as usize, winner)?;
+        
+        msg!("Bracket match {} won by {} over {}", node_index, winner, loser);
+        
+        emit!(BracketMatchReported {
+            tournament: tournament_state.key(),
+            node_index,
+            winner,
+            loser,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn publish_pairing(ctx: Context<PublishPairing>, round: u16) -> Result<()> {
+        let tournament_state = &mut ctx.accounts.tournament_state;
+        
+        require!(
+            tournament_state.phase == TournamentPhase::Playing,
+            ErrorCode::InvalidPhase
+        );
+        
+        require!(
+            tournament_state.format == TournamentFormat::Swiss,
+            ErrorCode::NotSwissTournament
+        );
+        
+        require!(
+            round == tournament_state.current_round,
+            ErrorCode::InvalidRound
+        );
+        
+        let entry_a = &mut ctx.accounts.entry_a;
+        require!(entry_a.last_paired_round < round, ErrorCode::PlayerAlreadyPaired);
+        entry_a.last_paired_round = round;
+        
+        let pairing = &mut ctx.accounts.pairing;
+        pairing.tournament = tournament_state.key();
+        pairing.round = round;
+        pairing.player_a = entry_a.player;
+        pairing.bump = ctx.bumps.pairing;
+        
+        if let Some(entry_b) = ctx.accounts.entry_b.as_mut() {
+            require!(entry_b.player != entry_a.player, ErrorCode::DuplicateMatchPlayer);
+            require!(entry_b.last_paired_round < round, ErrorCode::PlayerAlreadyPaired);
+            entry_b.last_paired_round = round;
+            
+            pairing.player_b = Some(entry_b.player);
+            pairing.result = None;
+            
+            record_score(&mut ctx.accounts.swiss_standings.records, entry_b.player, entry_b.points, entry_b.tiebreak);
+            
+            tournament_state.pending_results = tournament_state
+                .pending_results
+                .checked_add(1)
+                .ok_or(ErrorCode::CalculationOverflow)?;
+            
+            msg!("Round {} pairing: {} vs {}", round, entry_a.player, entry_b.player);
+        } else {
+            // A bye scores as a win straight away and adds nothing to the tiebreak
+            entry_a.points = entry_a.points.checked_add(WIN_POINTS).ok_or(ErrorCode::CalculationOverflow)?;
+            
+            pairing.player_b = None;
+            pairing.result = Some(SwissResult::PlayerAWins);
+            
+            msg!("Round {} bye: {}", round, entry_a.player);
+        }
+        
+        record_score(&mut ctx.accounts.swiss_standings.records, entry_a.player, entry_a.points, entry_a.tiebreak);
+        
+        emit!(SwissPairingPublished {
+            tournament: tournament_state.key(),
+            round,
+            player_a: pairing.player_a,
+            player_b: pairing.player_b,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn report_swiss_result(ctx: Context<ReportSwissResult>, round: u16, result: SwissResult) -> Result<()> {
+        let tournament_state = &mut ctx.accounts.tournament_state;
+        
+        require!(
+            tournament_state.phase == TournamentPhase::Playing,
+            ErrorCode::InvalidPhase
+        );
+        
+        require!(
+            round == tournament_state.current_round,
+            ErrorCode::InvalidRound
+        );
+        
+        let pairing = &mut ctx.accounts.pairing;
+        require!(pairing.result.is_none(), ErrorCode::MatchAlreadyReported);
+        
+        let entry_a = &mut ctx.accounts.entry_a;
+        let entry_b = &mut ctx.accounts.entry_b;
+        
+        // Each player's tiebreak grows by the opponent's points going into the round
+        let (earned_a, earned_b) = result.points();
+        let (points_before_a, points_before_b) = (entry_a.points, entry_b.points);
+        
+        entry_a.points = points_before_a.checked_add(earned_a).ok_or(ErrorCode::CalculationOverflow)?;
+        entry_a.tiebreak = entry_a.tiebreak.checked_add(points_before_b).ok_or(ErrorCode::CalculationOverflow)?;
+        entry_b.points = points_before_b.checked_add(earned_b).ok_or(ErrorCode::CalculationOverflow)?;
+        entry_b.tiebreak = entry_b.tiebreak.checked_add(points_before_a).ok_or(ErrorCode::CalculationOverflow)?;
+        
+        let records = &mut ctx.accounts.swiss_standings.records;
+        record_score(records, entry_a.player, entry_a.points, entry_a.tiebreak);
+        record_score(records, entry_b.player, entry_b.points, entry_b.tiebreak);
+        
+        pairing.result = Some(result);
+        tournament_state.pending_results = tournament_state
+            .pending_results
+            .checked_sub(1)
+            .ok_or(ErrorCode::CalculationOverflow)?;
+        
+        msg!("Round {} result reported: {} has {} points, {} has {} points", 
+             round, entry_a.player, entry_a.points, entry_b.player, entry_b.points);
+        
+        emit!(SwissResultReported {
+            tournament: tournament_state.key(),
+            round,
+            player_a: entry_a.player,
+            player_b: entry_b.player,
+            result,
+            points_a: entry_a.points,
+            points_b: entry_b.points,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn open_round(ctx: Context<OpenRound>) -> Result<()> {
+        let tournament_state = &mut ctx.accounts.tournament_state;
+        
+        require!(
+            tournament_state.phase == TournamentPhase::Playing,
+            ErrorCode::InvalidPhase
+        );
+        
+        require!(
+            tournament_state.format == TournamentFormat::Swiss,
+            ErrorCode::NotSwissTournament
+        );
+        
+        require!(
+            tournament_state.pending_results == 0,
+            ErrorCode::RoundResultsPending
+        );
+        
+        tournament_state.current_round = tournament_state
+            .current_round
+            .checked_add(1)
+            .ok_or(ErrorCode::CalculationOverflow)?;
+        
+        msg!("Swiss round {} opened", tournament_state.current_round);
+        
+        emit!(SwissRoundOpened {
+            tournament: tournament_state.key(),
+            round: tournament_state.current_round,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn commit_seating(ctx: Context<CommitSeating>, commitment: [u8; 32]) -> Result<()> {
+        let tournament_state = &mut ctx.accounts.tournament_state;
+        
+        require!(
+            tournament_state.phase == TournamentPhase::Playing,
+            ErrorCode::InvalidPhase
+        );
+        
+        // Seating only means something if it decides every match
+        require!(
+            tournament_state.matches_created == 0,
+            ErrorCode::MatchesAlreadyCreated
+        );
+        
+        let slot = Clock::get()?.slot;
+        
+        // One live commitment per tournament, so an unwelcome seating cannot be re-rolled. A
+        // commitment that can no longer be revealed would block match creation for good, so it
+        // may be replaced, and the lapsed one is published to keep the re-roll on record
+        match tournament_state.seating_commitment {
+            Some(lapsed_commitment) => {
+                require!(
+                    tournament_state.seating_seed.is_none()
+                        && commitment_lapsed(tournament_state.seating_commit_slot, slot),
+                    ErrorCode::SeatingAlreadyCommitted
+                );
+                
+                tournament_state.lapsed_seating_commitments = tournament_state
+                    .lapsed_seating_commitments
+                    .checked_add(1)
+                    .ok_or(ErrorCode::CalculationOverflow)?;
+                
+                msg!("Seating commitment from slot {} lapsed unrevealed", tournament_state.seating_commit_slot);
+                
+                emit!(SeatingCommitmentLapsed {
+                    tournament: tournament_state.key(),
+                    commitment: lapsed_commitment,
+                    slot: tournament_state.seating_commit_slot,
+                    lapsed_count: tournament_state.lapsed_seating_commitments,
+                    timestamp: Clock::get()?.unix_timestamp,
+                });
+            },
+            None => {
+                let seating = ctx.accounts.seating.as_mut().ok_or(ErrorCode::MissingSeating)?;
+                seating.tournament = tournament_state.key();
+                seating.bump = ctx.bumps.seating.unwrap_or_default();
+                seating.players = Vec::new();
+            }
+        }
+        
+        tournament_state.seating_commitment = Some(commitment);
+        tournament_state.seating_commit_slot = slot;
+        
+        msg!("Seating commitment recorded at slot {}", slot);
+        
+        emit!(SeatingCommitted {
+            tournament: tournament_state.key(),
+            commitment,
+            slot,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn seed_matches(ctx: Context<SeedMatches>, secret: [u8; 32]) -> Result<()> {
+        let tournament_state = &mut ctx.accounts.tournament_state;
+        let tournament_key = tournament_state.key();
+        
+        require!(
+            tournament_state.phase == TournamentPhase::Playing,
+            ErrorCode::InvalidPhase
+        );
+        
+        require!(
+            tournament_state.seating_seed.is_none(),
+            ErrorCode::MatchesAlreadySeeded
+        );
+        
+        let commitment = tournament_state.seating_commitment.ok_or(ErrorCode::SeatingNotCommitted)?;
+        require!(
+            seating_commitment(&tournament_key, &secret) == commitment,
+            ErrorCode::InvalidSeatingSecret
+        );
+        
+        // Past this point commit_seating may replace the commitment, so it can no longer be revealed
+        require!(
+            !commitment_lapsed(tournament_state.seating_commit_slot, Clock::get()?.slot),
+            ErrorCode::SeatingRevealExpired
+        );
+        
+        let (slot, slot_hash) = {
+            let slot_hashes_data = ctx.accounts.slot_hashes.try_borrow_data()?;
+            slot_hash_after(&slot_hashes_data, tournament_state.seating_commit_slot)?
+        };
+        let seed = seating_seed(&tournament_key, &secret, &slot_hash);
+        
+        tournament_state.seating_seed = Some(seed);
+        
+        msg!("Seating seed revealed using slot {}", slot);
+        
+        emit!(MatchesSeeded {
+            tournament: tournament_key,
+            seed,
+            slot,
+            slot_hash,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn enroll_seating<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, EnrollSeating<'info>>,
+        players: Vec<Pubkey>,
+    ) -> Result<()> {
+        let tournament_state = &mut ctx.accounts.tournament_state;
+        let tournament_key = tournament_state.key();
+        
+        require!(
+            tournament_state.phase == TournamentPhase::Playing,
+            ErrorCode::InvalidPhase
+        );
+        
+        require!(!tournament_state.seated, ErrorCode::MatchesAlreadySeeded);
+        let seed = tournament_state.seating_seed.ok_or(ErrorCode::SeatingNotRevealed)?;
+        
+        // remaining_accounts carry the entry of each player, in the same order
+        let seating = &mut ctx.accounts.seating;
+        for (index, player) in players.iter().enumerate() {
+            player_entry_at(ctx.remaining_accounts, index, &tournament_key, player)?;
+            require!(!seating.players.contains(player), ErrorCode::IncompleteSeating);
+            seating.players.push(*player);
+        }
+        
+        msg!("Enrolled {} of {} players for seating", 
+             seating.players.len(), tournament_state.current_players);
+        
+        // Anyone can crank the pages, and the page completing the field shuffles it, so the seats
+        // depend only on the revealed seed
+        if seating.players.len() == tournament_state.current_players as usize {
+            seating.players = seat_players(std::mem::take(&mut seating.players), &seed);
+            tournament_state.seated = true;
+            
+            msg!("Seated {} players in groups of {}", seating.players.len(), tournament_state.match_size);
+            
+            emit!(PlayersSeated {
+                tournament: tournament_key,
+                player_count: tournament_state.current_players,
+                match_size: tournament_state.match_size,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+        }
+        
+        Ok(())
+    }
+    pub fn close_entry(ctx: Context<CloseEntry>) -> Result<()> {
+        let player_entry = &ctx.accounts.player_entry;
+        let tournament_info = ctx.accounts.tournament_state.to_account_info();
+        
+        // Once the tournament itself is closed nothing is owed to anyone, so only a live tournament
+        // needs checking
+        if tournament_info.owner == &crate::ID {
+            let tournament_state = TournamentState::try_deserialize(&mut &tournament_info.try_borrow_data()?[..])?;
+            
+            match tournament_state.phase {
+                TournamentPhase::Cancelled => {
+                    require!(player_entry.refunded, ErrorCode::EntryNotSettled);
+                },
+                TournamentPhase::Finalized => {
+                    // Late match rewards can be recorded on the entry until the grace period is over
+                    let sweep_opens_at = tournament_state
+                        .finalized_at
+                        .checked_add(tournament_state.sweep_grace_period)
+                        .ok_or(ErrorCode::CalculationOverflow)?;
+                    require!(
+                        Clock::get()?.unix_timestamp >= sweep_opens_at,
+                        ErrorCode::SweepGracePeriodActive
+                    );
+                    require!(
+                        player_entry.prize_claimed == player_entry.prize_amount
+                            && player_entry.match_claimed == player_entry.match_rewards_amount,
+                        ErrorCode::EntryNotSettled
+                    );
+                    
+                    // A winner's entry weighs their share of the remainder until the tournament closes
+                    let remainder_to_winners = tournament_state.sweep_policy == SweepPolicy::Winners
+                        && tournament_state.merkle_root.is_none();
+                    require!(
+                        player_entry.prize_amount == 0 || !remainder_to_winners,
+                        ErrorCode::EntryNotSettled
+                    );
+                },
+                _ => return err!(ErrorCode::InvalidPhase),
+            }
+        }
+        
+        msg!("Entry of {} in tournament {} closed", player_entry.player, player_entry.tournament);
+        
+        emit!(EntryClosed {
+            tournament: player_entry.tournament,
+            player: player_entry.player,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn close_records<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, CloseRecords<'info>>) -> Result<()> {
+        let tournament_state = &ctx.accounts.tournament_state;
+        let tournament_key = tournament_state.key();
+        
+        match tournament_state.phase {
+            TournamentPhase::Finalized => {
+                // Match records back distribute_match_rewards until the grace period is over
+                let sweep_opens_at = tournament_state
+                    .finalized_at
+                    .checked_add(tournament_state.sweep_grace_period)
+                    .ok_or(ErrorCode::CalculationOverflow)?;
+                require!(
+                    Clock::get()?.unix_timestamp >= sweep_opens_at,
+                    ErrorCode::SweepGracePeriodActive
+                );
+            },
+            TournamentPhase::Cancelled => {},
+            _ => return err!(ErrorCode::InvalidPhase),
+        }
+        
+        // Match and pairing records are passed in remaining_accounts; both were paid for by the authority
+        for account in ctx.remaining_accounts.iter() {
+            require!(
+                account.owner == &crate::ID && account.is_writable,
+                ErrorCode::InvalidRecordAccount
+            );
+            
+            let record_tournament = {
+                let data = account.try_borrow_data()?;
+                match MatchState::try_deserialize(&mut &data[..]) {
+                    Ok(match_state) => match_state.tournament,
+                    Err(_) => SwissPairing::try_deserialize(&mut &data[..])
+                        .map_err(|_| ErrorCode::InvalidRecordAccount)?
+                        .tournament,
+                }
+            };
+            require_keys_eq!(record_tournament, tournament_key, ErrorCode::InvalidRecordAccount);
+            
+            close_program_account(account, &ctx.accounts.authority.to_account_info())?;
+        }
+        
+        msg!("Closed {} records of tournament {}", ctx.remaining_accounts.len(), tournament_key);
+        
+        emit!(RecordsClosed {
+            tournament: tournament_key,
+            record_count: ctx.remaining_accounts.len() as u32,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn seed_bracket_page<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, SeedBracketPage<'info>>,
+        players: Vec<Pubkey>,
+    ) -> Result<()> {
+        let tournament_state = &ctx.accounts.tournament_state;
+        let tournament_key = tournament_state.key();
+        
+        require!(
+            tournament_state.phase == TournamentPhase::Playing,
+            ErrorCode::InvalidPhase
+        );
+        
+        let bracket = &mut ctx.accounts.bracket;
+        let size = bracket.size as usize;
+        require!(
+            bracket.seeded as usize + players.len() <= tournament_state.current_players as usize,
+            ErrorCode::InvalidBracketSeeding
+        );
+        
+        // Pages continue the seed order, strongest first; remaining_accounts carry each player's
+        // entry in the same order
+        for (index, player) in players.iter().enumerate() {
+            player_entry_at(ctx.remaining_accounts, index, &tournament_key, player)?;
+            require!(!bracket.nodes[size - 1..].contains(player), ErrorCode::InvalidBracketSeeding);
+            
+            let leaf = seed_leaf(size, bracket.seeded as usize);
+            bracket.nodes[leaf] = *player;
+            bracket.seeded += 1;
+        }
+        
+        msg!("Seeded {} of {} players", bracket.seeded, tournament_state.current_players);
+        
+        if bracket.seeded == tournament_state.current_players {
+            advance_byes(&mut bracket.nodes);
+            
+            emit!(BracketSeeded {
+                tournament: tournament_key,
+                player_count: bracket.seeded,
+                size: bracket.size,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+        }
+        
+        Ok(())
+    }
+
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct TournamentState {
+    pub buy_in_amount: u64,    
+    pub max_players: u32,       
+    pub current_players: u32, 
+    pub escrow_bump: u8,       
+    pub match_size: u8,     
+    pub phase: TournamentPhase, 
+    pub tournament_prize_percentage: u16,  
+    pub match_prize_percentage: u16,    
+    pub operator_fee_percentage: u16,  
+    #[max_len(20)]
+    pub tournament_payouts: Vec<u32>, 
+    #[max_len(8)]
+    pub match_payout_percentages: Vec<u32>,
+    pub operator_fee_withdrawn: bool,  
+    pub authority: Pubkey,    
+    pub mint: Option<Pubkey>,
+    pub escrow_token_bump: u8,
+    pub net_buy_in_amount: u64,
+    pub operator: Pubkey,
+    pub tournament_id: u64,
+    pub bump: u8,
+    pub pending_authority: Option<Pubkey>,
+    pub merkle_root: Option<[u8; 32]>,
+    pub merkle_total: u64,
+    pub merkle_claimed_total: u64,
+    pub min_players: u32,
+    pub registration_closes_at: i64,
+    pub expired: bool,
+    pub unregister_penalty_percentage: u16,
+    pub forfeited_amount: u64,
+    pub max_play_duration: i64,
+    pub started_at: i64,
+    pub match_rewards_recorded: u64,
+    pub refund_per_player: u64,
+    pub prizes_recorded: u64,
+    pub refunded_players: u32,
+    pub tournament_paid: u64,
+    pub match_paid: u64,
+    pub operator_paid: u64,
+    pub refunds_paid: u64,
+    pub sweep_policy: SweepPolicy,
+    pub sweep_grace_period: i64,
+    pub finalized_at: i64,
+    pub remainder_swept: u64,
+    pub escrow_rent_reserve: u64,
+    pub payout_precision: PayoutPrecision,
+    pub tie_remainder_policy: TieRemainderPolicy,
+    pub format: TournamentFormat,
+    pub current_round: u16,
+    pub pending_results: u32,
+    pub seating_commitment: Option<[u8; 32]>,
+    pub seating_commit_slot: u64,
+    pub seating_seed: Option<[u8; 32]>,
+    pub seated: bool,
+    pub matches_created: u32,
+    pub unregister_refunds_paid: u64,
+    pub remainder_recorded: u64,
+    pub lapsed_seating_commitments: u16,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct PlayerEntry {
+    pub tournament: Pubkey,
+    pub player: Pubkey,
+    pub joined_at: i64,
+    pub refunded: bool,
+    pub claimed: bool,
+    pub prize_amount: u64,
+    pub match_rewards_amount: u64,
+    pub prize_claimed: u64,
+    pub match_claimed: u64,
+    pub bump: u8,
+    pub points: u32,
+    pub tiebreak: u32,
+    pub last_paired_round: u16,
+}
+
+impl PlayerEntry {
+    /// Marks everything recorded so far as claimed and returns the unclaimed tournament prize and
+    /// match rewards. Match rewards can be claimed while the tournament is still playing, so each
+    /// kind keeps its own counter and every claim is charged to the bucket it came from.
+    pub fn settle_claim(&mut self) -> Result<(u64, u64)> {
+        let prize_due = self
+            .prize_amount
+            .checked_sub(self.prize_claimed)
+            .ok_or(ErrorCode::CalculationOverflow)?;
+        let match_due = self
+            .match_rewards_amount
+            .checked_sub(self.match_claimed)
+            .ok_or(ErrorCode::CalculationOverflow)?;
+        
+        self.prize_claimed = self.prize_amount;
+        self.match_claimed = self.match_rewards_amount;
+        self.claimed = true;
+        
+        Ok((prize_due, match_due))
+    }
+}
+
+#[account]
+pub struct ClaimBitmap {
+    pub tournament: Pubkey,
+    pub leaf_count: u32,
+    pub bump: u8,
+    pub claimed: Vec<u8>,
+}
+
+impl ClaimBitmap {
+    pub fn bitmap_len(leaf_count: u32) -> usize {
+        (leaf_count as usize).div_ceil(8)
+    }
+
+    pub fn space(leaf_count: u32) -> usize {
+        8 + 32 + 4 + 1 + 4 + Self::bitmap_len(leaf_count)
+    }
+}
+
+#[account]
+pub struct MatchState {
+    pub tournament: Pubkey,
+    pub match_id: [u8; 32],
+    pub paid: bool,
+    pub paid_at: i64,
+    pub rewards_amount: u64,
+    pub bump: u8,
+    pub players: Vec<Pubkey>,
+}
+
+impl MatchState {
+    pub fn space(player_count: usize) -> usize {
+        8 + 32 + 32 + 1 + 8 + 8 + 1 + 4 + 32 * player_count
+    }
+}
+
+#[account]
+pub struct Bracket {
+    pub tournament: Pubkey,
+    pub size: u32,
+    pub bump: u8,
+    pub seeded: u32,
+    pub nodes: Vec<Pubkey>,
+}
+
+impl Bracket {
+    pub fn space(player_count: u32) -> usize {
+        let size = player_count.clamp(2, MAX_BRACKET_PLAYERS).next_power_of_two() as usize;
+        8 + 32 + 4 + 1 + 4 + 4 + 32 * (2 * size - 1)
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct SwissPairing {
+    pub tournament: Pubkey,
+    pub round: u16,
+    pub player_a: Pubkey,
+    pub player_b: Option<Pubkey>,
+    pub result: Option<SwissResult>,
+    pub bump: u8,
+}
+
+/// Every paired player's current Swiss score, kept up to date by each pairing and result so that
+/// standings never depend on loading every player entry at once.
+#[account]
+pub struct SwissStandings {
+    pub tournament: Pubkey,
+    pub bump: u8,
+    pub records: Vec<SwissRecord>,
+}
+
+impl SwissStandings {
+    pub fn space(record_count: usize) -> usize {
+        8 + 32 + 1 + 4 + SwissRecord::INIT_SPACE * record_count
+    }
+}
+
+/// Seating order of every player, filled page by page and shuffled once the field is complete.
+/// Seat `i` plays in group `i / match_size`.
+#[account]
+pub struct Seating {
+    pub tournament: Pubkey,
+    pub bump: u8,
+    pub players: Vec<Pubkey>,
+}
+
+impl Seating {
+    pub fn space(player_count: usize) -> usize {
+        8 + 32 + 1 + 4 + 32 * player_count
+    }
+}
+
+#[account]
+pub struct GlobalConfig {
+    pub admin: Pubkey,
+    pub pending_admin: Option<Pubkey>,
+    pub allowed_creators: Vec<Pubkey>,
+    pub max_operator_fee_percentage: u16,
+    pub min_tournament_prize_percentage: u16,
+    pub bump: u8,
+    pub treasury: Pubkey,
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + 32 + (1 + 32) + 4 + (32 * MAX_ALLOWED_CREATORS) + 2 + 2 + 1 + 32,
+        seeds = [b"config"],
+        bump,
+    )]
+    pub config: Account<'info, GlobalConfig>,
+    
+    #[account(
+        constraint = program.programdata_address()? == Some(program_data.key()) @ ErrorCode::UnauthorizedAuthority
+    )]
+    pub program: Program<'info, crate::program::Tournament>,
+    
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(admin.key()) @ ErrorCode::UnauthorizedAuthority
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.admin == admin.key() @ ErrorCode::UnauthorizedAuthority
+    )]
+    pub config: Account<'info, GlobalConfig>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.pending_admin == Some(new_admin.key()) @ ErrorCode::UnauthorizedAuthority
+    )]
+    pub config: Account<'info, GlobalConfig>,
+    
+    pub new_admin: Signer<'info>,
+}
+
+#[account]
+pub struct TournamentRegistry {
+    pub operator: Pubkey,
+    pub next_tournament_id: u64,
+    pub bump: u8,
+}
+
+#[derive(Accounts)]
+pub struct InitializeRegistry<'info> {
+    #[account(
+        init,
+        payer = operator,
+        space = 8 + 32 + 8 + 1,
+        seeds = [b"registry", operator.key().as_ref()],
+        bump,
+    )]
+    pub registry: Account<'info, TournamentRegistry>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.allowed_creators.contains(&operator.key()) @ ErrorCode::CreatorNotAllowed
+    )]
+    pub config: Account<'info, GlobalConfig>,
+    
+    #[account(mut)]
+    pub operator: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(
+    buy_in_amount: u64, 
+    max_players: u32, 
+    match_size: u8,
+    tournament_prize_percentage: u16,
+    match_prize_percentage: u16,
+    operator_fee_percentage: u16,
+    min_players: u32,
+    registration_closes_at: i64,
+    unregister_penalty_percentage: u16,
+    max_play_duration: i64,
+)]
+pub struct InitializeTournament<'info> {
+    #[account(
+        mut,
+        seeds = [b"registry", payer.key().as_ref()],
+        bump = registry.bump,
+    )]
+    pub registry: Account<'info, TournamentRegistry>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + TournamentState::INIT_SPACE,
+        seeds = [b"tournament", payer.key().as_ref(), &registry.next_tournament_id.to_le_bytes()],
+        bump,
+    )]
+    pub tournament_state: Account<'info, TournamentState>,
+   
+    #[account(
+        mut,
+        seeds = [b"escrow", tournament_state.key().as_ref()],
+        bump,
+    )]
+    /// CHECK: This is just a PDA that will hold funds
+    pub escrow_pda: UncheckedAccount<'info>,
+    
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"escrow_token", tournament_state.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = escrow_pda,
+        token::token_program = token_program,
+    )]
+    pub escrow_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.allowed_creators.contains(&payer.key()) @ ErrorCode::CreatorNotAllowed
+    )]
+    pub config: Account<'info, GlobalConfig>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BuyIn<'info> {
+    
+    #[account(mut)]
+    pub tournament_state: Account<'info, TournamentState>,
+    
+    #[account(
+        mut,
+        seeds = [b"escrow", tournament_state.key().as_ref()],
+        bump = tournament_state.escrow_bump,
+    )]
+    /// CHECK: This is just a PDA that will hold funds
+    pub escrow_pda: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"escrow_token", tournament_state.key().as_ref()],
+        bump = tournament_state.escrow_token_bump,
+    )]
+    pub escrow_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    #[account(
+        init,
+        payer = player,
+        space = 8 + PlayerEntry::INIT_SPACE,
+        seeds = [b"entry", tournament_state.key().as_ref(), player.key().as_ref()],
+        bump,
+    )]
+    pub player_entry: Account<'info, PlayerEntry>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    #[account(mut)]
+    pub player_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(
+        constraint = tournament_state.authority == authority.key() @ ErrorCode::UnauthorizedAuthority
+    )]
+    pub authority: Signer<'info>,
+    
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct StartTournament<'info> {
+    #[account(
+        mut,
+        constraint = tournament_state.authority == authority.key() @ ErrorCode::UnauthorizedAuthority
+    )]
+    pub tournament_state: Account<'info, TournamentState>,
+    
+    /// Only for single-elimination tournaments
+    #[account(
+        init,
+        payer = authority,
+        space = Bracket::space(tournament_state.current_players),
+        seeds = [b"bracket", tournament_state.key().as_ref()],
+        bump,
+    )]
+    pub bracket: Option<Account<'info, Bracket>>,
+    
+    /// Only for Swiss tournaments
+    #[account(
+        init,
+        payer = authority,
+        space = SwissStandings::space(0),
+        seeds = [b"standings", tournament_state.key().as_ref()],
+        bump,
+    )]
+    pub swiss_standings: Option<Account<'info, SwissStandings>>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeTournament<'info> {
+    #[account(
+        mut,
+        constraint = tournament_state.authority == authority.key() @ ErrorCode::UnauthorizedAuthority
+    )]
+    pub tournament_state: Account<'info, TournamentState>,
+    
+    #[account(
+        seeds = [b"bracket", tournament_state.key().as_ref()],
+        bump = bracket.bump,
+    )]
+    pub bracket: Option<Account<'info, Bracket>>,
+    
+    #[account(
+        seeds = [b"standings", tournament_state.key().as_ref()],
+        bump = swiss_standings.bump,
+    )]
+    pub swiss_standings: Option<Account<'info, SwissStandings>>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(match_id: [u8; 32])]
+pub struct DistributeMatchRewards<'info> {
+    #[account(
+        mut,
+        constraint = tournament_state.authority == authority.key() @ ErrorCode::UnauthorizedAuthority
+    )]
+    pub tournament_state: Account<'info, TournamentState>,
+    
+    #[account(
+        mut,
+        seeds = [b"match", tournament_state.key().as_ref(), match_id.as_ref()],
+        bump = match_state.bump,
+        constraint = !match_state.paid @ ErrorCode::MatchAlreadyPaid
+    )]
+    pub match_state: Account<'info, MatchState>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawOperatorFee<'info> {
+    #[account(
+        mut,
+        constraint = tournament_state.authority == authority.key() @ ErrorCode::UnauthorizedAuthority
+    )]
+    pub tournament_state: Account<'info, TournamentState>,
+    
+    #[account(
+        mut,
+        seeds = [b"escrow", tournament_state.key().as_ref()],
+        bump = tournament_state.escrow_bump,
+    )]
+    /// CHECK: This is the escrow account that holds funds
+    pub escrow_pda: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"escrow_token", tournament_state.key().as_ref()],
+        bump = tournament_state.escrow_token_bump,
+    )]
+    pub escrow_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    pub authority: Signer<'info>,
+    
+    #[account(mut)]
+    /// CHECK: This is the destination account for the operator fee
+    pub fee_recipient: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub fee_recipient_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelTournament<'info> {
+    #[account(
+        mut,
+        constraint = tournament_state.authority == authority.key() @ ErrorCode::UnauthorizedAuthority
+    )]
+    pub tournament_state: Account<'info, TournamentState>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RefundParticipant<'info> {
+    #[account(mut)]
+    pub tournament_state: Account<'info, TournamentState>,
+    
+    #[account(
+        mut,
+        seeds = [b"escrow", tournament_state.key().as_ref()],
+        bump = tournament_state.escrow_bump,
+    )]
+    /// CHECK: This is the escrow account that holds funds
+    pub escrow_pda: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"escrow_token", tournament_state.key().as_ref()],
+        bump = tournament_state.escrow_token_bump,
+    )]
+    pub escrow_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    /// Either the participant or a cranker; funds only ever go to `participant`
+    pub caller: Signer<'info>,
+    
+    #[account(mut)]
+    /// CHECK: This is the participant account to refund
+    pub participant: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"entry", tournament_state.key().as_ref(), participant.key().as_ref()],
+        bump = player_entry.bump,
+        constraint = !player_entry.refunded @ ErrorCode::ParticipantAlreadyRefunded
+    )]
+    pub player_entry: Account<'info, PlayerEntry>,
+    
+    #[account(mut)]
+    pub participant_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeAuthority<'info> {
+    #[account(
+        mut,
+        constraint = tournament_state.authority == authority.key() @ ErrorCode::UnauthorizedAuthority
+    )]
+    pub tournament_state: Account<'info, TournamentState>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    #[account(
+        mut,
+        constraint = tournament_state.pending_authority == Some(new_authority.key()) @ ErrorCode::UnauthorizedAuthority
+    )]
+    pub tournament_state: Account<'info, TournamentState>,
+    
+    pub new_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimPrize<'info> {
+    #[account(mut)]
+    pub tournament_state: Account<'info, TournamentState>,
+    
+    #[account(
+        mut,
+        seeds = [b"escrow", tournament_state.key().as_ref()],
+        bump = tournament_state.escrow_bump,
+    )]
+    /// CHECK: This is the escrow account that holds funds
+    pub escrow_pda: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"escrow_token", tournament_state.key().as_ref()],
+        bump = tournament_state.escrow_token_bump,
+    )]
+    pub escrow_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    #[account(
+        mut,
+        seeds = [b"entry", tournament_state.key().as_ref(), player.key().as_ref()],
+        bump = player_entry.bump,
+    )]
+    pub player_entry: Account<'info, PlayerEntry>,
+    
+    #[account(mut)]
+    /// CHECK: Prize recipient, bound to the entry through its seeds
+    pub player: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub player_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    /// Either the player or a cranker claiming on their behalf
+    pub claimer: Signer<'info>,
+    
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(merkle_root: [u8; 32], total_amount: u64, leaf_count: u32)]
+pub struct FinalizeTournamentWithMerkleRoot<'info> {
+    #[account(
+        mut,
+        constraint = tournament_state.authority == authority.key() @ ErrorCode::UnauthorizedAuthority
+    )]
+    pub tournament_state: Account<'info, TournamentState>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = ClaimBitmap::space(leaf_count),
+        seeds = [b"claims", tournament_state.key().as_ref()],
+        bump,
+    )]
+    pub claim_bitmap: Account<'info, ClaimBitmap>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimWithProof<'info> {
+    #[account(mut)]
+    pub tournament_state: Account<'info, TournamentState>,
+    
+    #[account(
+        mut,
+        seeds = [b"claims", tournament_state.key().as_ref()],
+        bump = claim_bitmap.bump,
+    )]
+    pub claim_bitmap: Account<'info, ClaimBitmap>,
+    
+    #[account(
+        mut,
+        seeds = [b"escrow", tournament_state.key().as_ref()],
+        bump = tournament_state.escrow_bump,
+    )]
+    /// CHECK: This is the escrow account that holds funds
+    pub escrow_pda: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"escrow_token", tournament_state.key().as_ref()],
+        bump = tournament_state.escrow_token_bump,
+    )]
+    pub escrow_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    #[account(
+        mut,
+        seeds = [b"entry", tournament_state.key().as_ref(), player.key().as_ref()],
+        bump = player_entry.bump,
+    )]
+    pub player_entry: Account<'info, PlayerEntry>,
+    
+    #[account(mut)]
+    /// CHECK: Prize recipient, authenticated by the merkle leaf and entry seeds
+    pub player: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub player_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    /// Either the player or a cranker claiming on their behalf
+    pub claimer: Signer<'info>,
+    
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExpireTournament<'info> {
+    #[account(mut)]
+    pub tournament_state: Account<'info, TournamentState>,
+}
+
+#[derive(Accounts)]
+pub struct Unregister<'info> {
+    #[account(mut)]
+    pub tournament_state: Account<'info, TournamentState>,
+    
+    #[account(
+        mut,
+        seeds = [b"escrow", tournament_state.key().as_ref()],
+        bump = tournament_state.escrow_bump,
+    )]
+    /// CHECK: This is the escrow account that holds funds
+    pub escrow_pda: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"escrow_token", tournament_state.key().as_ref()],
+        bump = tournament_state.escrow_token_bump,
+    )]
+    pub escrow_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    #[account(
+        mut,
+        close = player,
+        seeds = [b"entry", tournament_state.key().as_ref(), player.key().as_ref()],
+        bump = player_entry.bump,
+    )]
+    pub player_entry: Account<'info, PlayerEntry>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    #[account(mut)]
+    pub player_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct EmergencyCancel<'info> {
+    #[account(mut)]
+    pub tournament_state: Account<'info, TournamentState>,
+    
+    /// The tournament authority, or anyone once the play duration has timed out
+    pub caller: Signer<'info>,
 }
 
 #[derive(Accounts)]
-#[instruction(
-    buy_in_amount: u64, 
-    max_players: u8, 
-    match_size: u8,
-    tournament_prize_percentage: u16,
-    match_prize_percentage: u16,
-    operator_fee_percentage: u16,
-)]
-pub struct InitializeTournament<'info> {
+pub struct CloseTournament<'info> {
     #[account(
-        init,
-        payer = payer,
-        space = 8 + 8 + 1 + 1 + 1 + 1 + 1 + 4 + (32 * 100) + 4 + (4 * 50) + 2 + 2 + 2 + 4 + (2 * 20) + 4 + (2 * 8) + 1 + 32 + 4 + (32 * 100)
+        mut,
+        close = authority,
+        constraint = tournament_state.authority == authority.key() @ ErrorCode::UnauthorizedAuthority
     )]
     pub tournament_state: Account<'info, TournamentState>,
-   
+    
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"claims", tournament_state.key().as_ref()],
+        bump = claim_bitmap.bump,
+    )]
+    pub claim_bitmap: Option<Account<'info, ClaimBitmap>>,
+    
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"bracket", tournament_state.key().as_ref()],
+        bump = bracket.bump,
+    )]
+    pub bracket: Option<Account<'info, Bracket>>,
+    
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"standings", tournament_state.key().as_ref()],
+        bump = swiss_standings.bump,
+    )]
+    pub swiss_standings: Option<Account<'info, SwissStandings>>,
+    
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"seating", tournament_state.key().as_ref()],
+        bump = seating.bump,
+    )]
+    pub seating: Option<Account<'info, Seating>>,
+    
     #[account(
+        mut,
         seeds = [b"escrow", tournament_state.key().as_ref()],
-        bump,
+        bump = tournament_state.escrow_bump,
     )]
-    /// CHECK: This is just a PDA that will hold funds
+    /// CHECK: This is the escrow account that holds funds
     pub escrow_pda: UncheckedAccount<'info>,
     
     #[account(
         mut,
-        constraint = payer.key().to_string() == PROGRAM_AUTHORITY @ ErrorCode::UnauthorizedAuthority
+        seeds = [b"escrow_token", tournament_state.key().as_ref()],
+        bump = tournament_state.escrow_token_bump,
     )]
-    pub payer: Signer<'info>,
+    pub escrow_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    // Writable so withheld Token-2022 transfer fees can be harvested into it
+    #[account(mut)]
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, GlobalConfig>,
+    
+    #[account(
+        mut,
+        address = config.treasury @ ErrorCode::InvalidTreasury
+    )]
+    /// CHECK: Receives the escrow remainder, validated against the global config
+    pub treasury: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    /// Receives the account rent; `operator` only seeds the tournament address and is not updated
+    /// by an authority handover
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub token_program: Option<Interface<'info, TokenInterface>>,
     
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct BuyIn<'info> {
-    
+pub struct SweepRemainder<'info> {
     #[account(mut)]
     pub tournament_state: Account<'info, TournamentState>,
     
@@ -910,33 +3874,121 @@ pub struct BuyIn<'info> {
         seeds = [b"escrow", tournament_state.key().as_ref()],
         bump = tournament_state.escrow_bump,
     )]
-    /// CHECK: This is just a PDA that will hold funds
+    /// CHECK: This is the escrow account that holds funds
     pub escrow_pda: UncheckedAccount<'info>,
     
+    #[account(
+        mut,
+        seeds = [b"escrow_token", tournament_state.key().as_ref()],
+        bump = tournament_state.escrow_token_bump,
+    )]
+    pub escrow_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, GlobalConfig>,
+    
     #[account(mut)]
-    pub player: Signer<'info>,
+    /// CHECK: Validated against the tournament's sweep policy in the handler; omitted when the
+    /// remainder is recorded for the winners
+    pub recipient: Option<UncheckedAccount<'info>>,
+    
+    #[account(mut)]
+    pub recipient_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    /// Anyone may sweep once the grace period is over; funds only go to the policy recipient
+    pub caller: Signer<'info>,
+    
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PreviewSettlement<'info> {
+    pub tournament_state: Account<'info, TournamentState>,
+    
+    #[account(
+        seeds = [b"bracket", tournament_state.key().as_ref()],
+        bump = bracket.bump,
+    )]
+    pub bracket: Option<Account<'info, Bracket>>,
     
     #[account(
+        seeds = [b"standings", tournament_state.key().as_ref()],
+        bump = swiss_standings.bump,
+    )]
+    pub swiss_standings: Option<Account<'info, SwissStandings>>,
+}
+
+#[derive(Accounts)]
+#[instruction(match_id: [u8; 32])]
+pub struct PreviewMatchRewards<'info> {
+    pub tournament_state: Account<'info, TournamentState>,
+    
+    #[account(
+        seeds = [b"match", tournament_state.key().as_ref(), match_id.as_ref()],
+        bump = match_state.bump,
+        constraint = !match_state.paid @ ErrorCode::MatchAlreadyPaid
+    )]
+    pub match_state: Account<'info, MatchState>,
+}
+
+#[derive(Accounts)]
+#[instruction(match_id: [u8; 32], players: Vec<Pubkey>)]
+pub struct CreateMatch<'info> {
+    #[account(
+        mut,
         constraint = tournament_state.authority == authority.key() @ ErrorCode::UnauthorizedAuthority
     )]
+    pub tournament_state: Account<'info, TournamentState>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = MatchState::space(players.len()),
+        seeds = [b"match", tournament_state.key().as_ref(), match_id.as_ref()],
+        bump,
+    )]
+    pub match_state: Account<'info, MatchState>,
+    
+    /// Required once players have been seated
+    #[account(
+        seeds = [b"seating", tournament_state.key().as_ref()],
+        bump = seating.bump,
+    )]
+    pub seating: Option<Account<'info, Seating>>,
+    
+    #[account(mut)]
     pub authority: Signer<'info>,
     
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct StartTournament<'info> {
+pub struct ReportMatchResult<'info> {
     #[account(
-        mut,
         constraint = tournament_state.authority == authority.key() @ ErrorCode::UnauthorizedAuthority
     )]
     pub tournament_state: Account<'info, TournamentState>,
     
+    #[account(
+        mut,
+        seeds = [b"bracket", tournament_state.key().as_ref()],
+        bump = bracket.bump,
+    )]
+    pub bracket: Account<'info, Bracket>,
+    
     pub authority: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct FinalizeTournament<'info> {
+#[instruction(round: u16)]
+pub struct PublishPairing<'info> {
     #[account(
         mut,
         constraint = tournament_state.authority == authority.key() @ ErrorCode::UnauthorizedAuthority
@@ -945,20 +3997,48 @@ pub struct FinalizeTournament<'info> {
     
     #[account(
         mut,
-        seeds = [b"escrow", tournament_state.key().as_ref()],
-        bump = tournament_state.escrow_bump,
+        seeds = [b"entry", tournament_state.key().as_ref(), entry_a.player.as_ref()],
+        bump = entry_a.bump,
     )]
-    /// CHECK: This is the escrow account that holds funds
-    pub escrow_pda: UncheckedAccount<'info>,
+    pub entry_a: Account<'info, PlayerEntry>,
+    
+    /// Omitted when `entry_a` receives a bye
+    #[account(
+        mut,
+        seeds = [b"entry", tournament_state.key().as_ref(), entry_b.player.as_ref()],
+        bump = entry_b.bump,
+    )]
+    pub entry_b: Option<Account<'info, PlayerEntry>>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + SwissPairing::INIT_SPACE,
+        seeds = [b"pairing", tournament_state.key().as_ref(), round.to_le_bytes().as_ref(), entry_a.player.as_ref()],
+        bump,
+    )]
+    pub pairing: Account<'info, SwissPairing>,
+    
+    /// Grows by up to two records as newly paired players enter the standings
+    #[account(
+        mut,
+        seeds = [b"standings", tournament_state.key().as_ref()],
+        bump = swiss_standings.bump,
+        realloc = SwissStandings::space(swiss_standings.records.len() + 2),
+        realloc::payer = authority,
+        realloc::zero = false,
+    )]
+    pub swiss_standings: Account<'info, SwissStandings>,
     
+    #[account(mut)]
     pub authority: Signer<'info>,
     
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(match_id_hash: u32, winners: Vec<Pubkey>)]
-pub struct DistributeMatchRewards<'info> {
+#[instruction(round: u16)]
+pub struct ReportSwissResult<'info> {
     #[account(
         mut,
         constraint = tournament_state.authority == authority.key() @ ErrorCode::UnauthorizedAuthority
@@ -967,77 +4047,153 @@ pub struct DistributeMatchRewards<'info> {
     
     #[account(
         mut,
-        seeds = [b"escrow", tournament_state.key().as_ref()],
-        bump = tournament_state.escrow_bump,
+        seeds = [b"pairing", tournament_state.key().as_ref(), round.to_le_bytes().as_ref(), pairing.player_a.as_ref()],
+        bump = pairing.bump,
     )]
-    /// CHECK: This is the escrow account that holds funds
-    pub escrow_pda: UncheckedAccount<'info>,
+    pub pairing: Account<'info, SwissPairing>,
     
-    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"entry", tournament_state.key().as_ref(), pairing.player_a.as_ref()],
+        bump = entry_a.bump,
+    )]
+    pub entry_a: Account<'info, PlayerEntry>,
     
-    pub system_program: Program<'info, System>,
+    #[account(
+        mut,
+        seeds = [b"entry", tournament_state.key().as_ref(), entry_b.player.as_ref()],
+        bump = entry_b.bump,
+        constraint = pairing.player_b == Some(entry_b.player) @ ErrorCode::WinnerNotInMatch
+    )]
+    pub entry_b: Account<'info, PlayerEntry>,
+    
+    #[account(
+        mut,
+        seeds = [b"standings", tournament_state.key().as_ref()],
+        bump = swiss_standings.bump,
+    )]
+    pub swiss_standings: Account<'info, SwissStandings>,
     
+    pub authority: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct WithdrawOperatorFee<'info> {
+pub struct OpenRound<'info> {
     #[account(
         mut,
         constraint = tournament_state.authority == authority.key() @ ErrorCode::UnauthorizedAuthority
     )]
     pub tournament_state: Account<'info, TournamentState>,
     
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CommitSeating<'info> {
     #[account(
         mut,
-        seeds = [b"escrow", tournament_state.key().as_ref()],
-        bump = tournament_state.escrow_bump,
+        constraint = tournament_state.authority == authority.key() @ ErrorCode::UnauthorizedAuthority
     )]
-    /// CHECK: This is the escrow account that holds funds
-    pub escrow_pda: UncheckedAccount<'info>,
+    pub tournament_state: Account<'info, TournamentState>,
     
-    pub authority: Signer<'info>,
+    /// Created with the first commitment; omitted when replacing a lapsed one
+    #[account(
+        init,
+        payer = authority,
+        space = Seating::space(0),
+        seeds = [b"seating", tournament_state.key().as_ref()],
+        bump,
+    )]
+    pub seating: Option<Account<'info, Seating>>,
     
     #[account(mut)]
-    /// CHECK: This is the destination account for the operator fee
-    pub fee_recipient: UncheckedAccount<'info>,
+    pub authority: Signer<'info>,
     
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct CancelTournament<'info> {
+pub struct SeedMatches<'info> {
     #[account(
         mut,
         constraint = tournament_state.authority == authority.key() @ ErrorCode::UnauthorizedAuthority
     )]
     pub tournament_state: Account<'info, TournamentState>,
     
+    #[account(address = slot_hashes::ID)]
+    /// CHECK: SlotHashes is too large to deserialize, so the raw data is read directly
+    pub slot_hashes: UncheckedAccount<'info>,
+    
     pub authority: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct RefundParticipant<'info> {
+#[instruction(players: Vec<Pubkey>)]
+pub struct EnrollSeating<'info> {
+    #[account(mut)]
+    pub tournament_state: Account<'info, TournamentState>,
+    
     #[account(
         mut,
-        constraint = tournament_state.authority == authority.key() @ ErrorCode::UnauthorizedAuthority
+        seeds = [b"seating", tournament_state.key().as_ref()],
+        bump = seating.bump,
+        realloc = Seating::space(seating.players.len() + players.len()),
+        realloc::payer = payer,
+        realloc::zero = false,
     )]
-    pub tournament_state: Account<'info, TournamentState>,
+    pub seating: Account<'info, Seating>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseEntry<'info> {
+    #[account(address = player_entry.tournament)]
+    /// CHECK: The entry's tournament, which may already be closed; read in the handler while it exists
+    pub tournament_state: UncheckedAccount<'info>,
     
     #[account(
         mut,
-        seeds = [b"escrow", tournament_state.key().as_ref()],
-        bump = tournament_state.escrow_bump,
+        close = player,
+        seeds = [b"entry", tournament_state.key().as_ref(), player.key().as_ref()],
+        bump = player_entry.bump,
     )]
-    /// CHECK: This is the escrow account that holds funds
-    pub escrow_pda: UncheckedAccount<'info>,
+    pub player_entry: Account<'info, PlayerEntry>,
     
-    pub authority: Signer<'info>,
+    /// Paid for the entry when joining, so gets the rent back
+    #[account(mut)]
+    pub player: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseRecords<'info> {
+    #[account(
+        constraint = tournament_state.authority == authority.key() @ ErrorCode::UnauthorizedAuthority
+    )]
+    pub tournament_state: Account<'info, TournamentState>,
     
     #[account(mut)]
-    /// CHECK: This is the participant account to refund
-    pub participant: UncheckedAccount<'info>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SeedBracketPage<'info> {
+    #[account(
+        constraint = tournament_state.authority == authority.key() @ ErrorCode::UnauthorizedAuthority
+    )]
+    pub tournament_state: Account<'info, TournamentState>,
     
-    pub system_program: Program<'info, System>,
+    #[account(
+        mut,
+        seeds = [b"bracket", tournament_state.key().as_ref()],
+        bump = bracket.bump,
+    )]
+    pub bracket: Account<'info, Bracket>,
+    
+    pub authority: Signer<'info>,
 }
 
 #[error_code]
@@ -1116,4 +4272,371 @@ pub enum ErrorCode {
     ParticipantNotFound,
     #[msg("Participant already refunded")]
     ParticipantAlreadyRefunded,
-}
\ No newline at end of file
+    #[msg("Token accounts are required for token-denominated tournaments")]
+    MissingTokenAccounts,
+    #[msg("Mint does not match the tournament mint")]
+    InvalidMint,
+    #[msg("Token account is not owned by the expected wallet")]
+    InvalidTokenAccount,
+    #[msg("Token transfer fee exceeds the amount fixed at tournament creation")]
+    TransferFeeExceeded,
+    #[msg("Creator is not allowed to create tournaments")]
+    CreatorNotAllowed,
+    #[msg("Creator is already allowed")]
+    CreatorAlreadyAllowed,
+    #[msg("Too many allowed creators")]
+    TooManyCreators,
+    #[msg("Invalid fee limits")]
+    InvalidFeeLimits,
+    #[msg("Player entry account must be writable")]
+    PlayerEntryNotWritable,
+    #[msg("Nothing to claim")]
+    NothingToClaim,
+    #[msg("Merkle settlement total exceeds the tournament prize pool")]
+    MerkleTotalExceedsPool,
+    #[msg("Tournament was not settled with a merkle root")]
+    MerkleRootNotSet,
+    #[msg("Invalid merkle proof")]
+    InvalidMerkleProof,
+    #[msg("Prize already claimed")]
+    AlreadyClaimed,
+    #[msg("Invalid minimum players")]
+    InvalidMinPlayers,
+    #[msg("Registration deadline must be in the future")]
+    InvalidRegistrationDeadline,
+    #[msg("Registration is closed")]
+    RegistrationClosed,
+    #[msg("Registration is still open")]
+    RegistrationStillOpen,
+    #[msg("Tournament reached its minimum player count")]
+    MinPlayersReached,
+    #[msg("Invalid unregister penalty percentage")]
+    InvalidUnregisterPenalty,
+    #[msg("Invalid max play duration")]
+    InvalidPlayDuration,
+    #[msg("Operator fee must be withdrawn before closing the tournament")]
+    OperatorFeeNotWithdrawn,
+    #[msg("Not all participants have been refunded")]
+    RefundsOutstanding,
+    #[msg("Not all recorded prizes have been claimed")]
+    PrizesOutstanding,
+    #[msg("Treasury does not match the global config")]
+    InvalidTreasury,
+    #[msg("Invalid sweep grace period")]
+    InvalidSweepGracePeriod,
+    #[msg("Sweep grace period has not elapsed yet")]
+    SweepGracePeriodActive,
+    #[msg("Sweep grace period has elapsed")]
+    SweepGracePeriodElapsed,
+    #[msg("Recipient does not match the tournament sweep policy")]
+    InvalidSweepRecipient,
+    #[msg("Nothing to sweep")]
+    NothingToSweep,
+    #[msg("Tournament prize payouts exceed the tournament pool")]
+    TournamentPayoutsExceeded,
+    #[msg("Match reward payouts exceed the match pool")]
+    MatchPayoutsExceeded,
+    #[msg("Operator payouts exceed the operator fee share")]
+    OperatorPayoutsExceeded,
+    #[msg("Refunds exceed the deposited buy-ins")]
+    RefundPayoutsExceeded,
+    #[msg("Total payouts exceed the escrow deposits")]
+    EscrowOverdrawn,
+    #[msg("Escrow balance would fall below the rent-exempt minimum")]
+    EscrowBelowRentExempt,
+    #[msg("Too many payouts to return from a preview")]
+    PreviewTooLarge,
+    #[msg("Share exceeds the whole pool")]
+    ShareOutOfRange,
+    #[msg("Invalid match roster")]
+    InvalidMatchRoster,
+    #[msg("Player appears twice in the match roster")]
+    DuplicateMatchPlayer,
+    #[msg("Winner did not play in this match")]
+    WinnerNotInMatch,
+    #[msg("Match rewards would exceed the match prize pool")]
+    MatchPoolExhausted,
+    #[msg("Invalid number of players for a bracket")]
+    InvalidBracketSize,
+    #[msg("Bracket account is required for single-elimination tournaments")]
+    MissingBracket,
+    #[msg("Bracket seeding must list every player entry exactly once")]
+    InvalidBracketSeeding,
+    #[msg("Invalid bracket node")]
+    InvalidBracketNode,
+    #[msg("Match result has already been reported")]
+    MatchAlreadyReported,
+    #[msg("Both players of this bracket match are not known yet")]
+    BracketMatchNotReady,
+    #[msg("Bracket has not been played to completion")]
+    BracketIncomplete,
+    #[msg("Standings are derived on-chain; winners must not be submitted")]
+    WinnersFromStandings,
+    #[msg("Tournament is not a Swiss tournament")]
+    NotSwissTournament,
+    #[msg("Round is not the current Swiss round")]
+    InvalidRound,
+    #[msg("Player is already paired this round")]
+    PlayerAlreadyPaired,
+    #[msg("Results are still pending for the current round")]
+    RoundResultsPending,
+    #[msg("Every player must be paired before standings are final")]
+    IncompleteStandings,
+    #[msg("Match roster does not match the seeded groups")]
+    RosterNotSeated,
+    #[msg("Seating has already been committed")]
+    SeatingAlreadyCommitted,
+    #[msg("Seating has not been committed")]
+    SeatingNotCommitted,
+    #[msg("Secret does not match the seating commitment")]
+    InvalidSeatingSecret,
+    #[msg("Matches have already been seeded")]
+    MatchesAlreadySeeded,
+    #[msg("Slot hash after the seating commitment is not available")]
+    SlotHashUnavailable,
+    #[msg("Seating pages must list each player entry once, in order")]
+    IncompleteSeating,
+    #[msg("Standings account is required for Swiss tournaments")]
+    MissingStandings,
+    #[msg("Seating seed has not been revealed")]
+    SeatingNotRevealed,
+    #[msg("Seating account is required once players are seated")]
+    MissingSeating,
+    #[msg("Matches cannot be created until the committed seating is complete")]
+    SeatingPending,
+    #[msg("Seating must be committed before any match is created")]
+    MatchesAlreadyCreated,
+    #[msg("Escrow remainder must be swept before closing")]
+    RemainderNotSwept,
+    #[msg("Every tournament prize winner's entry must be passed once, ordered by player")]
+    IncompleteWinnerEntries,
+    #[msg("Entry still has a refund, prize or remainder share to settle")]
+    EntryNotSettled,
+    #[msg("Account is not a writable match or pairing record of this tournament")]
+    InvalidRecordAccount,
+    #[msg("Every player must be seeded before bracket results are reported")]
+    BracketNotSeeded,
+    #[msg("Seating commitment can no longer be revealed; commit a new one")]
+    SeatingRevealExpired,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player_entry() -> PlayerEntry {
+        PlayerEntry {
+            tournament: Pubkey::new_unique(),
+            player: Pubkey::new_unique(),
+            joined_at: 0,
+            refunded: false,
+            claimed: false,
+            prize_amount: 0,
+            match_rewards_amount: 0,
+            prize_claimed: 0,
+            match_claimed: 0,
+            bump: 255,
+            points: 0,
+            tiebreak: 0,
+            last_paired_round: 0,
+        }
+    }
+
+    /// A standard tournament of `current_players` with 100-lamport buy-ins split 80% tournament
+    /// pool, 10% match pool and 10% operator fee.
+    fn tournament_state(current_players: u32) -> TournamentState {
+        TournamentState {
+            buy_in_amount: 100,
+            max_players: 16,
+            current_players,
+            escrow_bump: 255,
+            match_size: 2,
+            phase: TournamentPhase::Registration,
+            tournament_prize_percentage: 8_000,
+            match_prize_percentage: 1_000,
+            operator_fee_percentage: 1_000,
+            tournament_payouts: vec![10_000],
+            match_payout_percentages: vec![10_000],
+            operator_fee_withdrawn: false,
+            authority: Pubkey::new_unique(),
+            mint: None,
+            escrow_token_bump: 0,
+            net_buy_in_amount: 100,
+            operator: Pubkey::new_unique(),
+            tournament_id: 0,
+            bump: 255,
+            pending_authority: None,
+            merkle_root: None,
+            merkle_total: 0,
+            merkle_claimed_total: 0,
+            min_players: 2,
+            registration_closes_at: 0,
+            expired: false,
+            unregister_penalty_percentage: 1_000,
+            forfeited_amount: 0,
+            max_play_duration: 0,
+            started_at: 0,
+            match_rewards_recorded: 0,
+            refund_per_player: 0,
+            prizes_recorded: 0,
+            refunded_players: 0,
+            tournament_paid: 0,
+            match_paid: 0,
+            operator_paid: 0,
+            refunds_paid: 0,
+            sweep_policy: SweepPolicy::Treasury,
+            sweep_grace_period: 0,
+            finalized_at: 0,
+            remainder_swept: 0,
+            escrow_rent_reserve: 0,
+            payout_precision: PayoutPrecision::BasisPoints,
+            tie_remainder_policy: TieRemainderPolicy::PubkeyOrder,
+            format: TournamentFormat::Standard,
+            current_round: 0,
+            pending_results: 0,
+            seating_commitment: None,
+            seating_commit_slot: 0,
+            seating_seed: None,
+            seated: false,
+            matches_created: 0,
+            unregister_refunds_paid: 0,
+            remainder_recorded: 0,
+            lapsed_seating_commitments: 0,
+        }
+    }
+
+    #[test]
+    fn unregister_penalties_are_refunded_to_the_players_who_stay() {
+        // Three players joined and one left, paying a 10% penalty
+        let mut state = tournament_state(2);
+        state.forfeited_amount = 10;
+        state.unregister_refunds_paid = 90;
+        state.refunds_paid = 90;
+        check_escrow_invariants(&state).unwrap();
+
+        state.phase = TournamentPhase::Cancelled;
+        assert_eq!(calculate_cancellation_refund(&state).unwrap(), 105);
+
+        state.refunds_paid += 2 * 105;
+        state.refunded_players = 2;
+        check_escrow_invariants(&state).unwrap();
+        assert_eq!(calculate_outstanding_obligations(&state).unwrap(), 0);
+
+        // The escrow is empty, so not one more lamport can be refunded
+        state.refunds_paid += 1;
+        assert_eq!(check_escrow_invariants(&state).unwrap_err(), ErrorCode::RefundPayoutsExceeded.into());
+    }
+
+    #[test]
+    fn match_claims_during_play_come_out_of_the_emergency_refund() {
+        let mut state = tournament_state(4);
+        state.phase = TournamentPhase::Playing;
+
+        state.match_rewards_recorded = 20;
+        assert_eq!(calculate_outstanding_obligations(&state).unwrap(), 20);
+
+        state.match_paid = 20;
+        check_escrow_invariants(&state).unwrap();
+        assert_eq!(calculate_outstanding_obligations(&state).unwrap(), 0);
+
+        state.phase = TournamentPhase::Cancelled;
+        let refund = calculate_cancellation_refund(&state).unwrap();
+        assert_eq!(refund, 95);
+
+        state.refunds_paid = 4 * refund;
+        check_escrow_invariants(&state).unwrap();
+
+        // Refunding full buy-ins on top of the paid match reward would overdraw the escrow
+        state.refunds_paid = 400;
+        assert_eq!(check_escrow_invariants(&state).unwrap_err(), ErrorCode::EscrowOverdrawn.into());
+    }
+
+    #[test]
+    fn merkle_settlement_sweeps_only_what_nobody_is_owed() {
+        let mut state = tournament_state(4);
+        state.phase = TournamentPhase::Finalized;
+        state.merkle_root = Some([1; 32]);
+        state.merkle_total = 320;
+
+        // Every merkle claim plus the operator fee is still owed
+        assert_eq!(calculate_outstanding_obligations(&state).unwrap(), 360);
+
+        state.tournament_paid = 300;
+        state.operator_paid = 40;
+        state.operator_fee_withdrawn = true;
+        check_escrow_invariants(&state).unwrap();
+        assert_eq!(calculate_outstanding_obligations(&state).unwrap(), 20);
+
+        // The 60 left in escrow less the 20 still claimable is the unpaid match pool
+        let escrow_balance = 400 - 300 - 40;
+        let remainder = escrow_balance - calculate_outstanding_obligations(&state).unwrap();
+        assert_eq!(remainder, 40);
+        state.remainder_swept = remainder as u64;
+
+        state.tournament_paid = 320;
+        check_escrow_invariants(&state).unwrap();
+        assert_eq!(calculate_outstanding_obligations(&state).unwrap(), 0);
+
+        state.tournament_paid = 321;
+        assert_eq!(check_escrow_invariants(&state).unwrap_err(), ErrorCode::TournamentPayoutsExceeded.into());
+    }
+
+    #[test]
+    fn claims_are_charged_to_the_bucket_they_came_from() {
+        let mut entry = player_entry();
+
+        // A match reward claimed while the tournament is still playing
+        entry.match_rewards_amount = 20;
+        assert_eq!(entry.settle_claim().unwrap(), (0, 20));
+
+        // The tournament prize recorded at finalization is all tournament bucket
+        entry.prize_amount = 320;
+        assert_eq!(entry.settle_claim().unwrap(), (320, 0));
+
+        assert_eq!(entry.settle_claim().unwrap(), (0, 0));
+        assert_eq!((entry.prize_claimed, entry.match_claimed), (320, 20));
+    }
+
+    fn merkle_node(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+        let (low, high) = if a <= b { (a, b) } else { (b, a) };
+        hashv(&[&[1u8], &low, &high]).to_bytes()
+    }
+
+    #[test]
+    fn merkle_proofs_verify_each_claim_and_nothing_else() {
+        let players = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        let leaves: Vec<_> = players
+            .iter()
+            .enumerate()
+            .map(|(index, player)| merkle_leaf(index as u32, player, 100 * (index as u64 + 1)))
+            .collect();
+        let (left, right) = (merkle_node(leaves[0], leaves[1]), merkle_node(leaves[2], leaves[3]));
+        let root = merkle_node(left, right);
+
+        assert!(verify_merkle_proof(&[leaves[1], right], &root, leaves[0]));
+        assert!(verify_merkle_proof(&[leaves[2], left], &root, leaves[3]));
+
+        // Another amount, index or player gives a different leaf
+        assert!(!verify_merkle_proof(&[leaves[1], right], &root, merkle_leaf(0, &players[0], 101)));
+        assert!(!verify_merkle_proof(&[leaves[1], right], &root, merkle_leaf(1, &players[0], 100)));
+        assert!(!verify_merkle_proof(&[leaves[1], right], &root, merkle_leaf(0, &players[1], 100)));
+    }
+
+    #[test]
+    fn internal_nodes_are_not_accepted_as_leaves() {
+        let leaves = [merkle_leaf(0, &Pubkey::new_unique(), 100), merkle_leaf(1, &Pubkey::new_unique(), 200)];
+        let third = merkle_leaf(2, &Pubkey::new_unique(), 300);
+        let node = merkle_node(leaves[0], leaves[1]);
+        let root = merkle_node(node, third);
+
+        // Hashed up directly, the node and its sibling do reach the root
+        assert!(verify_merkle_proof(&[third], &root, node));
+
+        // A claim only enters the proof through merkle_leaf, whose prefix keeps the same bytes from
+        // ever hashing to a node
+        let (index, player, amount) = (0u32, Pubkey::new_from_array(leaves[0]), 200u64);
+        let as_node = hashv(&[&[1u8], &index.to_le_bytes(), player.as_ref(), &amount.to_le_bytes()]).to_bytes();
+        assert_ne!(merkle_leaf(index, &player, amount), as_node);
+        assert!(!verify_merkle_proof(&[third], &root, merkle_leaf(index, &player, amount)));
+    }
+}
@@ -0,0 +1,389 @@
+//! Payout planning shared by `finalize_tournament` and `distribute_match_rewards`.
+//!
+//! Everything here is pure arithmetic over a pool, a percentage table and the winner list, so it
+//! can be exercised off-chain without any accounts.
+
+use anchor_lang::prelude::*;
+
+use crate::{ErrorCode, Winner};
+
+/// Scale a payout table is expressed in. Basis points cover most tables; parts per million
+/// keeps very top-heavy tables (long tails of tiny positions) from rounding to zero.
+#[derive(Clone, Copy, PartialEq, AnchorSerialize, AnchorDeserialize, InitSpace)]
+pub enum PayoutPrecision {
+    BasisPoints,
+    PartsPerMillion,
+}
+
+impl PayoutPrecision {
+    /// Raw units that make up the whole pool at this precision.
+    pub fn scale(self) -> u32 {
+        match self {
+            PayoutPrecision::BasisPoints => BasisPoints::SCALE,
+            PayoutPrecision::PartsPerMillion => Ppm::SCALE,
+        }
+    }
+
+    /// Interprets a raw payout table entry at this precision.
+    pub fn share(self, units: u32) -> Result<Ppm> {
+        match self {
+            PayoutPrecision::BasisPoints => Ok(BasisPoints::new(units)?.into()),
+            PayoutPrecision::PartsPerMillion => Ppm::new(units),
+        }
+    }
+}
+
+/// What happens to the lamports left over when a tied group's combined prize does not divide
+/// evenly between its players.
+#[derive(Clone, Copy, PartialEq, AnchorSerialize, AnchorDeserialize, InitSpace)]
+pub enum TieRemainderPolicy {
+    /// One extra lamport each to the lowest pubkeys in the group, so the submission order of
+    /// tied players never matters.
+    PubkeyOrder,
+    /// Nobody receives the remainder; it stays in the escrow for `sweep_remainder`.
+    Sweep,
+}
+
+/// A share of a pool in basis points (1/10_000), never more than the whole pool.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BasisPoints(u32);
+
+impl BasisPoints {
+    pub const SCALE: u32 = 10_000;
+
+    pub fn new(units: impl Into<u32>) -> Result<Self> {
+        let units = units.into();
+        require!(units <= Self::SCALE, ErrorCode::ShareOutOfRange);
+        Ok(Self(units))
+    }
+
+    pub fn get(self) -> u32 {
+        self.0
+    }
+}
+
+/// A share of a pool in parts per million, never more than the whole pool. All payout
+/// arithmetic happens at this precision.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Ppm(u32);
+
+impl Ppm {
+    pub const SCALE: u32 = 1_000_000;
+
+    pub fn new(units: u32) -> Result<Self> {
+        require!(units <= Self::SCALE, ErrorCode::ShareOutOfRange);
+        Ok(Self(units))
+    }
+
+    pub fn get(self) -> u32 {
+        self.0
+    }
+
+    pub fn checked_add(self, other: Ppm) -> Result<Ppm> {
+        let units = self.0.checked_add(other.0).ok_or(ErrorCode::CalculationOverflow)?;
+        Ppm::new(units)
+    }
+}
+
+impl From<BasisPoints> for Ppm {
+    fn from(basis_points: BasisPoints) -> Self {
+        Ppm(basis_points.0 * (Ppm::SCALE / BasisPoints::SCALE))
+    }
+}
+
+pub fn calculate_total_buy_ins(current_players: u32, buy_in_amount: u64) -> Result<u128> {
+    (current_players as u128)
+        .checked_mul(buy_in_amount as u128)
+        .ok_or(ErrorCode::CalculationOverflow.into())
+}
+
+pub fn calculate_percentage_amount(total: u128, share: impl Into<Ppm>) -> Result<u128> {
+    let share = share.into();
+    let amount = total
+        .checked_mul(share.get() as u128)
+        .ok_or(ErrorCode::CalculationOverflow)?
+        / Ppm::SCALE as u128;
+    require!(
+        amount <= total,
+        ErrorCode::CalculationOverflow
+    );
+    Ok(amount)
+}
+
+/// Even share of `amount` for each of `ways` tied players, rounded down.
+pub fn split_evenly(amount: u128, ways: usize) -> Result<u128> {
+    amount
+        .checked_div(ways as u128)
+        .ok_or(ErrorCode::InvalidWinnerCount.into())
+}
+
+/// Splits `amount` in proportion to `weights`. Shares are rounded down and the lamports left over
+/// go one each to the earliest weights, so the shares always add up to `amount`.
+pub fn split_pro_rata(amount: u64, weights: &[u64]) -> Result<Vec<u64>> {
+    let total_weight: u128 = weights.iter().map(|weight| *weight as u128).sum();
+    require!(total_weight > 0, ErrorCode::InvalidWinnerCount);
+
+    let mut shares: Vec<u64> = weights
+        .iter()
+        .map(|weight| to_u64(amount as u128 * *weight as u128 / total_weight))
+        .collect::<Result<_>>()?;
+
+    // Each share loses less than one lamport to rounding, so fewer than `weights.len()` are left
+    let leftover = amount - shares.iter().sum::<u64>();
+    for share in shares.iter_mut().take(leftover as usize) {
+        *share += 1;
+    }
+
+    Ok(shares)
+}
+
+/// Splits `pool` across `winners` in finishing order. Each `Winner::Individual` takes the next
+/// entry of `percentages` (read at `precision`); a `Winner::Group` of tied players shares the
+/// combined entries of the positions it consumes, with any rounding remainder handled by
+/// `tie_remainder`. A player may only appear once, and winners may not consume positions past
+/// the end of the table.
+pub fn plan_payouts(
+    pool: u128,
+    percentages: &[u32],
+    precision: PayoutPrecision,
+    tie_remainder: TieRemainderPolicy,
+    winners: &[Winner],
+) -> Result<Vec<(Pubkey, u64)>> {
+    let all_players = winner_keys(winners);
+    for (index, player) in all_players.iter().enumerate() {
+        require!(
+            !all_players[..index].contains(player),
+            ErrorCode::DuplicateWinner
+        );
+    }
+
+    let mut payouts = Vec::new();
+    let mut position_counter = 0usize;
+    let mut total_planned = 0u128;
+
+    for winner in winners.iter() {
+        match winner {
+            Winner::Individual(player) => {
+                let &percentage = percentages
+                    .get(position_counter)
+                    .ok_or(ErrorCode::TooManyWinners)?;
+
+                let amount = calculate_percentage_amount(pool, precision.share(percentage)?)?;
+                payouts.push((*player, to_u64(amount)?));
+                total_planned += amount;
+                position_counter += 1;
+            },
+            Winner::Group(players, positions_consumed) => {
+                require!(
+                    !players.is_empty(),
+                    ErrorCode::InvalidWinnerCount
+                );
+
+                require!(
+                    *positions_consumed > 0,
+                    ErrorCode::InvalidWinnerCount
+                );
+
+                let positions_end = position_counter + *positions_consumed as usize;
+                require!(
+                    positions_end <= percentages.len(),
+                    ErrorCode::TooManyWinners
+                );
+
+                let mut combined_share = Ppm::new(0)?;
+                for &percentage in percentages[position_counter..positions_end].iter() {
+                    combined_share = combined_share.checked_add(precision.share(percentage)?)?;
+                }
+
+                let combined_amount = calculate_percentage_amount(pool, combined_share)?;
+                let payout_per_player = split_evenly(combined_amount, players.len())?;
+                let remainder = combined_amount - payout_per_player * players.len() as u128;
+
+                let mut players_by_key = players.clone();
+                players_by_key.sort();
+
+                for player in players.iter() {
+                    let rank = players_by_key
+                        .binary_search(player)
+                        .map_err(|_| ErrorCode::InvalidWinner)?;
+                    let receives_remainder_lamport =
+                        tie_remainder == TieRemainderPolicy::PubkeyOrder && (rank as u128) < remainder;
+                    let amount = payout_per_player + receives_remainder_lamport as u128;
+
+                    require!(
+                        amount > 0,
+                        ErrorCode::NoMatchRewards
+                    );
+
+                    payouts.push((*player, to_u64(amount)?));
+                    total_planned += amount;
+                }
+
+                position_counter = positions_end;
+            }
+        }
+    }
+
+    require!(
+        total_planned <= pool,
+        ErrorCode::CalculationOverflow
+    );
+
+    Ok(payouts)
+}
+
+/// Turns finishing levels, best first, into a winner list for the payout table. A level of one
+/// player is an individual placement and a larger level is a tied group; empty levels are skipped.
+/// Placements stop at `positions` paid places, and a group that straddles the cut shares whatever
+/// places are left.
+pub fn placements(levels: impl IntoIterator<Item = Vec<Pubkey>>, positions: usize) -> Vec<Winner> {
+    let mut winners = Vec::new();
+    let mut placed = 0;
+
+    for level in levels {
+        if placed >= positions {
+            break;
+        }
+
+        match level.len() {
+            0 => {},
+            1 => {
+                winners.push(Winner::Individual(level[0]));
+                placed += 1;
+            },
+            tied => {
+                let places = tied.min(positions - placed);
+                winners.push(Winner::Group(level, places as u8));
+                placed += places;
+            }
+        }
+    }
+
+    winners
+}
+
+/// Every winning player in finishing order, with tied groups expanded in place.
+pub fn winner_keys(winners: &[Winner]) -> Vec<Pubkey> {
+    winners.iter().flat_map(|w| match w {
+        Winner::Individual(p) => vec![*p],
+        Winner::Group(players, _) => players.clone(),
+    }).collect()
+}
+
+fn to_u64(amount: u128) -> Result<u64> {
+    u64::try_from(amount).map_err(|_| ErrorCode::CalculationOverflow.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::players;
+
+    fn plan(pool: u128, percentages: &[u32], winners: &[Winner]) -> Result<Vec<(Pubkey, u64)>> {
+        plan_payouts(pool, percentages, PayoutPrecision::BasisPoints, TieRemainderPolicy::PubkeyOrder, winners)
+    }
+
+    #[test]
+    fn individual_winners_take_their_positions() {
+        let p = players(2);
+        let winners = [Winner::Individual(p[0]), Winner::Individual(p[1])];
+
+        let payouts = plan(1_000, &[6_000, 4_000], &winners).unwrap();
+
+        assert_eq!(payouts, vec![(p[0], 600), (p[1], 400)]);
+    }
+
+    #[test]
+    fn group_shares_the_positions_it_consumes() {
+        let p = players(3);
+        let winners = [Winner::Individual(p[0]), Winner::Group(vec![p[1], p[2]], 2)];
+
+        let payouts = plan(1_000, &[5_000, 3_000, 2_000], &winners).unwrap();
+
+        assert_eq!(payouts, vec![(p[0], 500), (p[1], 250), (p[2], 250)]);
+    }
+
+    #[test]
+    fn duplicate_winners_are_rejected() {
+        let p = players(2);
+        let winners = [Winner::Individual(p[0]), Winner::Group(vec![p[0], p[1]], 1)];
+
+        let result = plan(1_000, &[5_000, 5_000], &winners);
+
+        assert_eq!(result.unwrap_err(), ErrorCode::DuplicateWinner.into());
+    }
+
+    #[test]
+    fn winners_past_the_table_are_rejected() {
+        let p = players(3);
+        let individuals = [Winner::Individual(p[0]), Winner::Individual(p[1]), Winner::Individual(p[2])];
+        let group = [Winner::Individual(p[0]), Winner::Group(vec![p[1], p[2]], 2)];
+
+        assert_eq!(plan(1_000, &[6_000, 4_000], &individuals).unwrap_err(), ErrorCode::TooManyWinners.into());
+        assert_eq!(plan(1_000, &[6_000, 4_000], &group).unwrap_err(), ErrorCode::TooManyWinners.into());
+    }
+
+    #[test]
+    fn parts_per_million_pays_positions_basis_points_cannot() {
+        let p = players(2);
+        let winners = [Winner::Individual(p[0]), Winner::Individual(p[1])];
+
+        let payouts = plan_payouts(1_000_000, &[999_950, 50], PayoutPrecision::PartsPerMillion, TieRemainderPolicy::PubkeyOrder, &winners).unwrap();
+
+        assert_eq!(payouts, vec![(p[0], 999_950), (p[1], 50)]);
+    }
+
+    #[test]
+    fn shares_above_the_scale_are_rejected() {
+        let p = players(1);
+        let winners = [Winner::Individual(p[0])];
+
+        let basis_points = plan_payouts(1_000, &[10_001], PayoutPrecision::BasisPoints, TieRemainderPolicy::PubkeyOrder, &winners);
+        let parts_per_million = plan_payouts(1_000, &[1_000_001], PayoutPrecision::PartsPerMillion, TieRemainderPolicy::PubkeyOrder, &winners);
+
+        assert_eq!(basis_points.unwrap_err(), ErrorCode::ShareOutOfRange.into());
+        assert_eq!(parts_per_million.unwrap_err(), ErrorCode::ShareOutOfRange.into());
+    }
+
+    #[test]
+    fn pubkey_order_gives_the_remainder_to_the_lowest_keys() {
+        let p = players(3);
+        // Submitted out of key order; the remainder still follows the keys
+        let winners = [Winner::Group(vec![p[2], p[0], p[1]], 1)];
+
+        let payouts = plan_payouts(101, &[10_000], PayoutPrecision::BasisPoints, TieRemainderPolicy::PubkeyOrder, &winners).unwrap();
+
+        assert_eq!(payouts, vec![(p[2], 33), (p[0], 34), (p[1], 34)]);
+    }
+
+    #[test]
+    fn sweep_leaves_the_remainder_in_escrow() {
+        let p = players(3);
+        let winners = [Winner::Group(vec![p[0], p[1], p[2]], 1)];
+
+        let payouts = plan_payouts(101, &[10_000], PayoutPrecision::BasisPoints, TieRemainderPolicy::Sweep, &winners).unwrap();
+
+        assert_eq!(payouts, vec![(p[0], 33), (p[1], 33), (p[2], 33)]);
+    }
+
+    #[test]
+    fn placements_cut_a_straddling_group_at_the_paid_places() {
+        let p = players(5);
+        let levels = vec![vec![p[0]], vec![], vec![p[1], p[2], p[3]], vec![p[4]]];
+
+        let winners = placements(levels, 3);
+
+        assert_eq!(winners.len(), 2);
+        assert!(matches!(&winners[0], Winner::Individual(player) if *player == p[0]));
+        assert!(matches!(&winners[1], Winner::Group(group, 2) if *group == vec![p[1], p[2], p[3]]));
+    }
+
+    #[test]
+    fn pro_rata_shares_follow_the_weights_and_add_up() {
+        assert_eq!(split_pro_rata(100, &[600, 300, 100]).unwrap(), vec![60, 30, 10]);
+        // 10 * 2/3 and 10 * 1/3 round down to 6 and 3; the leftover lamport goes to the first
+        assert_eq!(split_pro_rata(10, &[2, 1]).unwrap(), vec![7, 3]);
+        assert_eq!(split_pro_rata(2, &[1, 1, 1]).unwrap(), vec![1, 1, 0]);
+        assert_eq!(split_pro_rata(5, &[0, 0]).unwrap_err(), ErrorCode::InvalidWinnerCount.into());
+    }
+}
@@ -0,0 +1,135 @@
+//! Verifiable random seating.
+//!
+//! The operator commits to a secret, and the seed mixes that secret with the hash of the first
+//! slot after the commitment, which nobody knew when the secret was chosen. Players are ordered
+//! by pubkey, shuffled with the seed and cut into consecutive groups of `match_size`, so anyone
+//! holding the seed and the player list can recompute every seat.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::solana_program::slot_hashes::MAX_ENTRIES;
+
+use crate::ErrorCode;
+
+/// Size of one `(slot, hash)` entry in the `SlotHashes` sysvar.
+const SLOT_HASH_ENTRY_LEN: usize = 8 + 32;
+
+pub fn seating_commitment(tournament: &Pubkey, secret: &[u8; 32]) -> [u8; 32] {
+    hashv(&[b"seating", tournament.as_ref(), secret]).to_bytes()
+}
+
+pub fn seating_seed(tournament: &Pubkey, secret: &[u8; 32], slot_hash: &[u8; 32]) -> [u8; 32] {
+    hashv(&[tournament.as_ref(), secret, slot_hash]).to_bytes()
+}
+
+/// Finds the hash of the earliest slot after `slot` in raw `SlotHashes` data (newest first). The
+/// sysvar only covers the last 512 slots, so the hash is unavailable both before any later slot
+/// has been recorded and once `slot` has aged out of the window.
+pub fn slot_hash_after(data: &[u8], slot: u64) -> Result<(u64, [u8; 32])> {
+    let entry_count = data
+        .get(..8)
+        .map(|len| u64::from_le_bytes(len.try_into().unwrap()))
+        .ok_or(ErrorCode::SlotHashUnavailable)?;
+
+    let mut earliest_after = None;
+    for entry in data[8..].chunks_exact(SLOT_HASH_ENTRY_LEN).take(entry_count as usize) {
+        let entry_slot = u64::from_le_bytes(entry[..8].try_into().unwrap());
+        if entry_slot <= slot {
+            return earliest_after.ok_or(ErrorCode::SlotHashUnavailable.into());
+        }
+        earliest_after = Some((entry_slot, entry[8..].try_into().unwrap()));
+    }
+
+    err!(ErrorCode::SlotHashUnavailable)
+}
+
+/// Whether a commitment made at `commit_slot` can no longer be revealed at `current_slot`. The
+/// sysvar holds the last `MAX_ENTRIES` slots that produced a block, so it always reaches back to
+/// the commitment within that many slots; past them the reveal is treated as expired, whatever
+/// skipped slots may have left in the window.
+pub fn commitment_lapsed(commit_slot: u64, current_slot: u64) -> bool {
+    current_slot.saturating_sub(commit_slot) > MAX_ENTRIES as u64
+}
+
+/// Seating order of `players` for `seed`: a Fisher-Yates shuffle of the players sorted by pubkey,
+/// so the order they were submitted in has no effect.
+pub fn seat_players(mut players: Vec<Pubkey>, seed: &[u8; 32]) -> Vec<Pubkey> {
+    players.sort();
+
+    for i in (1..players.len()).rev() {
+        let draw = hashv(&[seed, &(i as u64).to_le_bytes()]).to_bytes();
+        let j = u64::from_le_bytes(draw[..8].try_into().unwrap()) % (i as u64 + 1);
+        players.swap(i, j as usize);
+    }
+
+    players
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slot_hashes(entries: &[(u64, [u8; 32])]) -> Vec<u8> {
+        let mut data = (entries.len() as u64).to_le_bytes().to_vec();
+        for (slot, hash) in entries {
+            data.extend_from_slice(&slot.to_le_bytes());
+            data.extend_from_slice(hash);
+        }
+        data
+    }
+
+    #[test]
+    fn seating_is_a_deterministic_permutation_independent_of_input_order() {
+        let players: Vec<Pubkey> = (0..9).map(|_| Pubkey::new_unique()).collect();
+        let mut reversed = players.clone();
+        reversed.reverse();
+
+        let seating = seat_players(players.clone(), &[7; 32]);
+
+        assert_eq!(seating, seat_players(reversed, &[7; 32]));
+        assert_ne!(seating, seat_players(players.clone(), &[8; 32]));
+
+        let mut seated = seating.clone();
+        seated.sort();
+        let mut expected = players;
+        expected.sort();
+        assert_eq!(seated, expected);
+    }
+
+    #[test]
+    fn slot_hash_after_picks_the_first_slot_past_the_commitment() {
+        // Newest first, with slot 102 skipped
+        let data = slot_hashes(&[(105, [5; 32]), (103, [3; 32]), (101, [1; 32]), (100, [0; 32])]);
+
+        assert_eq!(slot_hash_after(&data, 101).unwrap(), (103, [3; 32]));
+        assert_eq!(slot_hash_after(&data, 102).unwrap(), (103, [3; 32]));
+        assert_eq!(slot_hash_after(&data, 104).unwrap(), (105, [5; 32]));
+    }
+
+    #[test]
+    fn slot_hash_after_is_unavailable_outside_the_window() {
+        let data = slot_hashes(&[(105, [5; 32]), (103, [3; 32])]);
+
+        // Nothing recorded after the commitment yet
+        assert_eq!(slot_hash_after(&data, 105).unwrap_err(), ErrorCode::SlotHashUnavailable.into());
+        // The commitment slot has aged out, so the first slot after it is unknown
+        assert_eq!(slot_hash_after(&data, 100).unwrap_err(), ErrorCode::SlotHashUnavailable.into());
+        assert_eq!(slot_hash_after(&[], 100).unwrap_err(), ErrorCode::SlotHashUnavailable.into());
+    }
+
+    #[test]
+    fn commitments_lapse_once_the_window_has_passed() {
+        assert!(!commitment_lapsed(1_000, 1_000));
+        assert!(!commitment_lapsed(1_000, 1_000 + MAX_ENTRIES as u64));
+        assert!(commitment_lapsed(1_000, 1_001 + MAX_ENTRIES as u64));
+    }
+
+    #[test]
+    fn commitments_and_seeds_are_bound_to_the_tournament() {
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let secret = [42; 32];
+
+        assert_ne!(seating_commitment(&first, &secret), seating_commitment(&second, &secret));
+        assert_ne!(seating_seed(&first, &secret, &[1; 32]), seating_seed(&second, &secret, &[1; 32]));
+    }
+}
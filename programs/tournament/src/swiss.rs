@@ -0,0 +1,132 @@
+//! Swiss-system scoring and standings.
+//!
+//! Players earn `WIN_POINTS` for a win or a bye and `DRAW_POINTS` for a draw. Players level on
+//! points are separated by opponent strength: every reported result adds the opponent's points
+//! going into that round to the player's tiebreak score.
+
+use anchor_lang::prelude::*;
+
+use crate::payout::placements;
+use crate::Winner;
+
+pub const WIN_POINTS: u32 = 2;
+pub const DRAW_POINTS: u32 = 1;
+
+#[derive(Clone, Copy, PartialEq, AnchorSerialize, AnchorDeserialize, InitSpace)]
+pub enum SwissResult {
+    PlayerAWins,
+    PlayerBWins,
+    Draw,
+}
+
+impl SwissResult {
+    /// Points earned by player A and player B respectively.
+    pub fn points(self) -> (u32, u32) {
+        match self {
+            SwissResult::PlayerAWins => (WIN_POINTS, 0),
+            SwissResult::PlayerBWins => (0, WIN_POINTS),
+            SwissResult::Draw => (DRAW_POINTS, DRAW_POINTS),
+        }
+    }
+}
+
+/// A player's accumulated Swiss score.
+#[derive(Clone, AnchorSerialize, AnchorDeserialize, InitSpace)]
+pub struct SwissRecord {
+    pub player: Pubkey,
+    pub points: u32,
+    pub tiebreak: u32,
+}
+
+/// Stores the latest score for `player`, adding a record the first time the player is paired.
+pub fn record_score(records: &mut Vec<SwissRecord>, player: Pubkey, points: u32, tiebreak: u32) {
+    match records.iter_mut().find(|record| record.player == player) {
+        Some(record) => {
+            record.points = points;
+            record.tiebreak = tiebreak;
+        },
+        None => records.push(SwissRecord { player, points, tiebreak }),
+    }
+}
+
+/// Placements from accumulated scores: by points, then tiebreak, with players level on both
+/// forming a tied group.
+pub fn standings(mut records: Vec<SwissRecord>, positions: usize) -> Vec<Winner> {
+    records.sort_by(|a, b| {
+        b.points
+            .cmp(&a.points)
+            .then(b.tiebreak.cmp(&a.tiebreak))
+            .then(a.player.cmp(&b.player))
+    });
+
+    let mut levels: Vec<Vec<Pubkey>> = Vec::new();
+    let mut previous_score = None;
+
+    for record in records.iter() {
+        let score = (record.points, record.tiebreak);
+        match levels.last_mut() {
+            Some(level) if previous_score == Some(score) => level.push(record.player),
+            _ => levels.push(vec![record.player]),
+        }
+        previous_score = Some(score);
+    }
+
+    placements(levels, positions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{keys, players};
+
+    #[test]
+    fn results_award_win_and_draw_points() {
+        assert_eq!(SwissResult::PlayerAWins.points(), (WIN_POINTS, 0));
+        assert_eq!(SwissResult::PlayerBWins.points(), (0, WIN_POINTS));
+        assert_eq!(SwissResult::Draw.points(), (DRAW_POINTS, DRAW_POINTS));
+    }
+
+    #[test]
+    fn record_score_updates_players_in_place() {
+        let p = players(2);
+        let mut records = Vec::new();
+
+        record_score(&mut records, p[0], 0, 0);
+        record_score(&mut records, p[1], 0, 0);
+        record_score(&mut records, p[0], 2, 1);
+
+        assert_eq!(records.len(), 2);
+        assert_eq!((records[0].player, records[0].points, records[0].tiebreak), (p[0], 2, 1));
+    }
+
+    #[test]
+    fn standings_rank_by_points_then_tiebreak() {
+        let p = players(4);
+        let records = vec![
+            SwissRecord { player: p[0], points: 2, tiebreak: 1 },
+            SwissRecord { player: p[1], points: 4, tiebreak: 0 },
+            SwissRecord { player: p[2], points: 2, tiebreak: 3 },
+            SwissRecord { player: p[3], points: 0, tiebreak: 4 },
+        ];
+
+        let winners: Vec<_> = standings(records, 3).iter().map(keys).collect();
+
+        assert_eq!(winners, vec![(vec![p[1]], 1), (vec![p[2]], 1), (vec![p[0]], 1)]);
+    }
+
+    #[test]
+    fn players_level_on_both_scores_tie_at_the_cut() {
+        let p = players(4);
+        let records = vec![
+            SwissRecord { player: p[3], points: 4, tiebreak: 2 },
+            SwissRecord { player: p[2], points: 2, tiebreak: 2 },
+            SwissRecord { player: p[0], points: 2, tiebreak: 2 },
+            SwissRecord { player: p[1], points: 2, tiebreak: 2 },
+        ];
+
+        let winners: Vec<_> = standings(records, 2).iter().map(keys).collect();
+
+        // Tied players are listed by pubkey and share the one place left
+        assert_eq!(winners, vec![(vec![p[3]], 1), (vec![p[0], p[1], p[2]], 1)]);
+    }
+}
@@ -0,0 +1,20 @@
+//! Fixtures shared by the unit tests of the payout, bracket and Swiss modules.
+
+use anchor_lang::prelude::*;
+
+use crate::Winner;
+
+/// `count` distinct players, sorted so tests can rely on pubkey order.
+pub fn players(count: usize) -> Vec<Pubkey> {
+    let mut players: Vec<Pubkey> = (0..count).map(|_| Pubkey::new_unique()).collect();
+    players.sort();
+    players
+}
+
+/// A winner's players and the positions it takes, so winner lists can be compared.
+pub fn keys(winner: &Winner) -> (Vec<Pubkey>, u8) {
+    match winner {
+        Winner::Individual(player) => (vec![*player], 1),
+        Winner::Group(players, positions) => (players.clone(), *positions),
+    }
+}